@@ -0,0 +1,103 @@
+//! Remembers the last country found by the geo-IP service, so it is not asked on every run
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use etcetera::BaseStrategy as _;
+use eyre::ContextCompat as _;
+use eyre::Result;
+use jiff::SignedDuration;
+use jiff::Timestamp;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// How long the cached country is used for, when the user does not choose
+pub const DEFAULT_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Contents of the cache file
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    /// URL of the geo-IP service that found the country
+    url: String,
+    /// 2-letter country code (cca2)
+    cca2: String,
+    /// When the country was detected, as a Unix timestamp in seconds
+    detected_at: i64,
+}
+
+/// A country that was previously detected
+pub struct Cached {
+    /// 2-letter country code (cca2)
+    pub cca2: String,
+    /// How long ago the country was detected
+    pub age: SignedDuration,
+}
+
+/// The cache file, e.g. `~/.cache/countryfetch/country.json`
+pub struct Cache {
+    path: PathBuf,
+    /// After this long, the cached country is stale
+    pub ttl: Duration,
+}
+
+impl Cache {
+    /// Cache in the user's cache directory, which does not exist on every system
+    pub fn new(ttl: Duration) -> Option<Self> {
+        let strategy = etcetera::choose_base_strategy().ok()?;
+
+        Some(Self {
+            path: strategy
+                .cache_dir()
+                .join("countryfetch")
+                .join("country.json"),
+            ttl,
+        })
+    }
+
+    /// The last country detected by the geo-IP service at `url`, even when it is stale.
+    /// A country found by a different service is not used
+    pub fn load(&self, url: &str) -> Option<Cached> {
+        let entry = serde_json::from_str::<Entry>(&fs::read_to_string(&self.path).ok()?).ok()?;
+
+        if entry.url != url {
+            return None;
+        }
+
+        let detected_at = Timestamp::from_second(entry.detected_at).ok()?;
+
+        Some(Cached {
+            cca2: entry.cca2,
+            age: Timestamp::now().duration_since(detected_at),
+        })
+    }
+
+    /// Remember `cca2` as the last country detected by the geo-IP service at `url`
+    pub fn store(&self, url: &str, cca2: &str) -> Result<()> {
+        let entry = Entry {
+            url: url.to_string(),
+            cca2: cca2.to_string(),
+            detected_at: Timestamp::now().as_second(),
+        };
+
+        fs::create_dir_all(self.path.parent().wrap_err("cache file has no parent")?)?;
+        fs::write(&self.path, serde_json::to_string(&entry)?)?;
+
+        Ok(())
+    }
+}
+
+impl Cached {
+    /// Whether this country was detected too long ago to be used without asking again
+    pub fn is_stale(&self, ttl: Duration) -> bool {
+        SignedDuration::try_from(ttl).is_ok_and(|ttl| self.age > ttl)
+    }
+
+    /// Where the country came from, for reporting to the user
+    pub fn describe(&self) -> String {
+        // Sub-second precision is just noise
+        let age = SignedDuration::from_secs(self.age.as_secs());
+
+        format!("the cache, detected {age:#} ago")
+    }
+}
@@ -10,17 +10,134 @@ use crate::country_display;
 use crate::country_display::AnsiRgb;
 
 #[derive(Parser)]
-#[command(version, about, styles = STYLES)]
+#[command(version, about, styles = STYLES, args_conflicts_with_subcommands = true)]
 pub struct Cli {
-    /// Country to print. (case-insensitive)
-    #[clap(ignore_case = true, hide_possible_values = true, help = format!("Country to show data for. {BOLD}Choose any of:{NO_BOLD}\n\n{}", display_possible_countries()))]
-    pub country: Option<crate::gen_countries::CountryKind>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// Countries to print, in the given order. (case-insensitive)
+    #[clap(ignore_case = true, hide_possible_values = true, help = format!("Countries to show data for. {BOLD}Choose any of:{NO_BOLD}\n\n{}", display_possible_countries()))]
+    pub country: Vec<crate::gen_countries::CountryKind>,
     /// Print all countries
-    #[arg(short, long)]
+    #[arg(short, long, conflicts_with = "country")]
     pub all: bool,
-    /// Print in JSON format
+    /// Only print countries that match an expression, such as
+    /// `region == "Europe" && landlocked && population > 5000000` or `"eng" in languages`.
+    /// Without any countries, all of them are filtered
+    #[arg(long = "where", value_name = "EXPRESSION")]
+    pub filter: Option<crate::filter::Filter>,
+    /// Sort the countries. Without any countries, all of them are sorted
+    #[arg(long, value_enum, value_name = "KEY")]
+    pub sort_by: Option<crate::sort::SortKey>,
+    /// Sort in descending order, largest first
+    #[arg(long, requires = "sort_by")]
+    pub desc: bool,
+    /// Print at most this many countries
+    #[arg(long, value_name = "COUNT")]
+    pub limit: Option<usize>,
+    /// Print the country of an IPv4 or IPv6 address, found with the geo-IP database or service
+    #[arg(long, value_name = "ADDRESS", conflicts_with_all = ["country", "all"])]
+    pub ip: Option<std::net::IpAddr>,
+    /// Print the countries that a dialing code or phone number belongs to, such as `+44`
+    /// or `+1 876 555 0100`
+    #[arg(long, value_name = "CODE", conflicts_with_all = ["country", "all", "ip"])]
+    pub dialing_code: Option<crate::dialing_code::DialingCode>,
+    /// How to print the countries
+    #[arg(long, value_enum, default_value_t, conflicts_with = "json")]
+    pub format: crate::export::Format,
+    /// Print in JSON format, same as `--format json`
     #[arg(short, long)]
     pub json: bool,
+    /// Print one row per country, with columns that fit the width of the terminal.
+    /// Choose the columns with --fields
+    #[arg(long, conflicts_with_all = ["json", "format"])]
+    pub table: bool,
+    /// In JSON, YAML and TOML output, show each neighbour as an object with its
+    /// codes, name and flag instead of just its cca3 code
+    #[arg(long)]
+    pub expand_borders: bool,
+    /// Print a single value without any formatting, e.g. `capital`, `idd` or `currencies.JPY.symbol`.
+    /// Exits with an error when the value does not exist
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["json", "format", "table", "fields", "hide"])]
+    pub get: Option<String>,
+    /// Print each country with a template file instead, such as
+    /// `{name.common} has {population|human} people`
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["json", "format", "table", "get"])]
+    pub template: Option<std::path::PathBuf>,
+    /// How population, area and density are written. Thousands separators follow your locale
+    #[arg(long, value_enum, default_value_t, global = true)]
+    pub numbers: crate::numbers::Notation,
+    /// Units of area, density and length
+    #[arg(long, value_enum, default_value_t, global = true)]
+    pub units: crate::units::Units,
+    /// Show the time in each timezone at this moment instead of now, such as
+    /// `2026-10-18 09:00` or `09:00` in your timezone
+    #[arg(long, value_name = "TIME", value_parser = crate::timezones::parse_moment, global = true)]
+    pub at: Option<jiff::Timestamp>,
+    /// Show the names of countries and their neighbours in this language, such as `fra` or `jpn`.
+    /// Countries without a translation keep their English name
+    #[arg(long, value_name = "LANGUAGE", value_parser = crate::names::parse_language, global = true)]
+    pub lang: Option<String>,
+    /// Only show these fields, in the given order
+    #[arg(long, value_enum, value_delimiter = ',', value_name = "FIELDS")]
+    pub fields: Option<Vec<crate::fields::Field>>,
+    /// Never show these fields
+    #[arg(long, value_enum, value_delimiter = ',', value_name = "FIELDS")]
+    pub hide: Vec<crate::fields::Field>,
+    /// How to detect your country when none is given. Strategies are tried in order,
+    /// until one of them succeeds [default: ip,locale,timezone or, with --geoip-db, database,locale,timezone]
+    #[arg(long, value_enum, value_delimiter = ',', value_name = "STRATEGIES")]
+    pub detect: Vec<crate::detect::Strategy>,
+    /// URL of the geo-IP service used to detect your country. `{ip}` is replaced by the address
+    /// to look up [default: `https://api.country.is/{ip}`]
+    #[arg(long, value_name = "URL")]
+    pub geoip_url: Option<String>,
+    /// Shape of the response returned by the geo-IP service [default: auto]
+    #[arg(long, value_enum, value_name = "SCHEMA")]
+    pub geoip_schema: Option<crate::geoip::Schema>,
+    /// Local geo-IP database, either a MaxMind DB (`.mmdb`) or a DB-IP CSV (`.csv`) file
+    #[arg(long, value_name = "PATH")]
+    pub geoip_db: Option<std::path::PathBuf>,
+    /// Give up on connecting to the geo-IP service after this long [default: 3s]
+    #[arg(long, value_name = "DURATION", value_parser = crate::config::parse_duration)]
+    pub connect_timeout: Option<std::time::Duration>,
+    /// Give up on waiting for the response of the geo-IP service after this long [default: 5s]
+    #[arg(long, value_name = "DURATION", value_parser = crate::config::parse_duration)]
+    pub read_timeout: Option<std::time::Duration>,
+    /// How many more times to ask the geo-IP service when it fails, waiting longer each time [default: 2]
+    #[arg(long, value_name = "COUNT")]
+    pub retries: Option<u32>,
+    /// How long the country found by the geo-IP service is cached for, e.g. `30m` or `1 day` [default: 1h]
+    #[arg(long, value_name = "DURATION", value_parser = crate::config::parse_duration)]
+    pub cache_ttl: Option<std::time::Duration>,
+    /// Ask the geo-IP service again, even if the cached country is still fresh
+    #[arg(long)]
+    pub refresh: bool,
+    /// Path to the config file [default: countryfetch/config.toml in the config directory]
+    #[arg(long, value_name = "PATH")]
+    pub config: Option<std::path::PathBuf>,
+    /// Explain how your country was detected
+    #[arg(short, long)]
+    pub verbose: bool,
+}
+
+#[derive(clap::Subcommand)]
+pub enum Command {
+    /// List every country that can be passed as an argument
+    #[command(long_flag = "list-countries")]
+    List {
+        /// How to print the list
+        #[arg(short, long, value_enum, default_value_t)]
+        format: crate::list_countries::ListFormat,
+    },
+    /// Show countries side by side, e.g. `countryfetch compare germany france poland`
+    Compare {
+        /// Countries to compare
+        #[arg(required = true, num_args = 2.., ignore_case = true, hide_possible_values = true)]
+        countries: Vec<crate::gen_countries::CountryKind>,
+        /// Print the differences between the countries in JSON format
+        #[arg(short, long)]
+        json: bool,
+    },
 }
 
 /// Styles for the CLI
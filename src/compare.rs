@@ -0,0 +1,270 @@
+//! Implements `countryfetch compare`, which shows countries side by side
+
+use std::io::Write as _;
+
+use serde_json::Value;
+use serde_json::json;
+use simply_colored::*;
+
+use crate::countries::Country;
+use crate::country_display;
+use crate::country_display::Options;
+use crate::fields::Field;
+use crate::sort;
+
+/// A row of the comparison
+#[derive(Clone, Copy)]
+enum Row {
+    Area,
+    Population,
+    Density,
+    Currencies,
+    Languages,
+    DrivingSide,
+    Timezones,
+}
+
+impl Row {
+    const ALL: [Self; 7] = [
+        Self::Area,
+        Self::Population,
+        Self::Density,
+        Self::Currencies,
+        Self::Languages,
+        Self::DrivingSide,
+        Self::Timezones,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Area => "Area",
+            Self::Population => "Population",
+            Self::Density => "Density",
+            Self::Currencies => "Currencies",
+            Self::Languages => "Languages",
+            Self::DrivingSide => "Driving side",
+            Self::Timezones => "Timezones",
+        }
+    }
+
+    /// Key of the row in the JSON output
+    fn key(self) -> &'static str {
+        match self {
+            Self::Area => "area",
+            Self::Population => "population",
+            Self::Density => "density",
+            Self::Currencies => "currencies",
+            Self::Languages => "languages",
+            Self::DrivingSide => "drivingSide",
+            Self::Timezones => "timezones",
+        }
+    }
+
+    /// Value of this row for a country, as shown in the table
+    fn text(self, country: &Country, options: &Options) -> String {
+        let field = |field| {
+            country_display::field_value(country, field, options)
+                .map(|(_, value)| value)
+                .unwrap_or_else(|| "-".to_string())
+        };
+
+        match self {
+            Self::Area => field(Field::Area),
+            Self::Population => field(Field::Population),
+            Self::Density => field(Field::Density),
+            Self::Currencies => field(Field::Currencies),
+            Self::Languages => field(Field::Languages),
+            Self::DrivingSide => field(Field::DrivingSide),
+            Self::Timezones => field(Field::Timezones),
+        }
+    }
+
+    /// Value of this row for a country, as data
+    fn json(self, country: &Country) -> Value {
+        match self {
+            Self::Area => json!(country.area),
+            Self::Population => json!(country.population),
+            Self::Density => json!(sort::density(country)),
+            Self::Currencies => {
+                let mut codes = country
+                    .currencies
+                    .iter()
+                    .flat_map(|currencies| currencies.keys())
+                    .collect::<Vec<_>>();
+                codes.sort();
+                json!(codes)
+            }
+            Self::Languages => {
+                let mut languages = country
+                    .languages
+                    .iter()
+                    .flat_map(|languages| languages.values())
+                    .collect::<Vec<_>>();
+                languages.sort();
+                json!(languages)
+            }
+            Self::DrivingSide => json!(country.car.side),
+            Self::Timezones => json!(country.timezones),
+        }
+    }
+}
+
+/// Print the countries side by side, one column per country
+pub fn print(countries: &[&Country], options: &Options) -> eyre::Result<()> {
+    let mut stdout = anstream::stdout().lock();
+
+    let label_width = Row::ALL
+        .iter()
+        .map(|row| row.label().len())
+        .max()
+        .unwrap_or_default();
+
+    let flag_width = |country: &Country| {
+        country
+            .flag_ascii_plain
+            .lines()
+            .next()
+            .map(textwrap::core::display_width)
+            .unwrap_or_default()
+    };
+
+    // Each column is as wide as the widest flag
+    let column_width = countries
+        .iter()
+        .map(|country| flag_width(country))
+        .max()
+        .unwrap_or_default()
+        .max(20);
+
+    let gap = "   ";
+    let label_padding = " ".repeat(label_width);
+
+    // Header: name of each country, underlined in the color of its flag
+    let mut names = String::new();
+    let mut separators = String::new();
+    for country in countries {
+        let color = country_display::brightest_color(country);
+        let name = crate::names::name(country, options);
+        let separator = "─".repeat(textwrap::core::display_width(name));
+        let name = match &country.flag {
+            Some(flag) => format!("{name} {flag}"),
+            None => name.to_string(),
+        };
+
+        names.push_str(gap);
+        names.push_str(&pad(&format!("{BOLD}{color}{name}{RESET}"), column_width));
+        separators.push_str(gap);
+        separators.push_str(&pad(&format!("{color}{separator}{RESET}"), column_width));
+    }
+    writeln!(stdout, "\n{label_padding}{}", names.trim_end())?;
+    writeln!(stdout, "{label_padding}{}", separators.trim_end())?;
+
+    // Flags, aligned to the top
+    let flag_height = countries
+        .iter()
+        .map(|country| country.flag_ascii_colored.lines().count())
+        .max()
+        .unwrap_or_default();
+
+    for i in 0..flag_height {
+        let mut line = String::new();
+        for country in countries {
+            line.push_str(gap);
+            line.push_str(&pad(
+                country
+                    .flag_ascii_colored
+                    .lines()
+                    .nth(i)
+                    .unwrap_or_default(),
+                column_width,
+            ));
+        }
+        writeln!(stdout, "{label_padding}{}", line.trim_end())?;
+    }
+
+    writeln!(stdout)?;
+
+    // One row for each field, where long values wrap within their column
+    for row in Row::ALL {
+        let cells = countries
+            .iter()
+            .map(|country| {
+                textwrap::wrap(
+                    &row.text(country, options),
+                    textwrap::Options::new(column_width - 2)
+                        .word_splitter(textwrap::WordSplitter::NoHyphenation),
+                )
+                .into_iter()
+                .map(|line| line.into_owned())
+                .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let height = cells.iter().map(Vec::len).max().unwrap_or_default();
+
+        for i in 0..height {
+            let label = if i == 0 { row.label() } else { "" };
+            let mut line = format!("{BOLD}{label:<label_width$}{RESET}");
+
+            for (country, cell) in countries.iter().zip(&cells) {
+                let color = country_display::brightest_color(country);
+                let value = cell.get(i).map(String::as_str).unwrap_or_default();
+
+                line.push_str(gap);
+                // Accent of the country, so each column is easy to follow
+                line.push_str(&format!("{color}│{RESET} "));
+                line.push_str(&pad(value, column_width.saturating_sub(2)));
+            }
+
+            writeln!(stdout, "{}", line.trim_end())?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Print the comparison as JSON. Fields that are the same for all countries are
+/// under `same`, and fields with differences list the value for each country under `different`
+pub fn print_json(countries: &[&Country]) -> eyre::Result<()> {
+    let mut same = serde_json::Map::new();
+    let mut different = serde_json::Map::new();
+
+    for row in Row::ALL {
+        let values = countries
+            .iter()
+            .map(|country| (country.cca3.clone(), row.json(country)))
+            .collect::<serde_json::Map<_, _>>();
+
+        let mut unique = values.values();
+        let first = unique.next();
+
+        if unique.all(|value| Some(value) == first) {
+            same.insert(row.key().to_string(), first.cloned().unwrap_or_default());
+        } else {
+            different.insert(row.key().to_string(), Value::Object(values));
+        }
+    }
+
+    let comparison = json!({
+        "countries": countries
+            .iter()
+            .map(|country| json!({ "cca3": country.cca3, "name": country.name.common }))
+            .collect::<Vec<_>>(),
+        "same": same,
+        "different": different,
+    });
+
+    writeln!(
+        anstream::stdout().lock(),
+        "{}",
+        colored_json::to_colored_json_auto(&comparison)?
+    )?;
+
+    Ok(())
+}
+
+/// Pad `text` with spaces until it is `width` columns wide, ignoring ANSI escape sequences
+fn pad(text: &str, width: usize) -> String {
+    let padding = width.saturating_sub(textwrap::core::display_width(text));
+    format!("{text}{}", " ".repeat(padding))
+}
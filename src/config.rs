@@ -0,0 +1,102 @@
+//! User configuration, read from `countryfetch/config.toml` in the config directory
+//!
+//! Every option can also be set on the command line, which takes precedence.
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use etcetera::BaseStrategy as _;
+use eyre::Result;
+use eyre::WrapErr as _;
+use jiff::Span;
+use jiff::SpanRelativeTo;
+use serde::Deserialize;
+
+use crate::geoip;
+
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    /// URL of the geo-IP service that is used to detect the country
+    pub geoip_url: Option<String>,
+    /// Shape of the response returned by `geoip_url`
+    pub geoip_schema: Option<geoip::Schema>,
+    /// Local geo-IP database, used instead of `geoip_url`
+    pub geoip_db: Option<PathBuf>,
+    /// How long the country found by the geo-IP service is cached for
+    #[serde(deserialize_with = "deserialize_duration")]
+    pub cache_ttl: Option<Duration>,
+    /// Give up on connecting to the geo-IP service after this long
+    #[serde(deserialize_with = "deserialize_duration")]
+    pub connect_timeout: Option<Duration>,
+    /// Give up on waiting for the response of the geo-IP service after this long
+    #[serde(deserialize_with = "deserialize_duration")]
+    pub read_timeout: Option<Duration>,
+    /// How many more times to ask the geo-IP service, when it fails
+    pub retries: Option<u32>,
+    /// Template file used instead of the default output
+    pub template: Option<PathBuf>,
+}
+
+impl Config {
+    /// Read the config file at `path`. When `path` is not given, the default location
+    /// is used instead, and a missing file is the same as an empty one
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let (path, required) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => {
+                match default_path() {
+                    Some(path) => (path, false),
+                    None => return Ok(Self::default()),
+                }
+            }
+        };
+
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(error) if !required && error.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Self::default());
+            }
+            Err(error) => {
+                return Err(error)
+                    .wrap_err_with(|| format!("failed to read config file {}", path.display()));
+            }
+        };
+
+        toml::from_str(&contents)
+            .wrap_err_with(|| format!("invalid config file {}", path.display()))
+    }
+}
+
+/// Location of the config file, e.g. `~/.config/countryfetch/config.toml`
+fn default_path() -> Option<PathBuf> {
+    let strategy = etcetera::choose_base_strategy().ok()?;
+
+    Some(
+        strategy
+            .config_dir()
+            .join("countryfetch")
+            .join("config.toml"),
+    )
+}
+
+/// Parse a duration such as `500ms`, `30m` or `1 day`
+pub fn parse_duration(duration: &str) -> Result<Duration, String> {
+    let duration = duration
+        .parse::<Span>()
+        .and_then(|span| span.to_duration(SpanRelativeTo::days_are_24_hours()))
+        .map_err(|error| error.to_string())?;
+
+    Duration::try_from(duration).map_err(|_| "duration cannot be negative".to_string())
+}
+
+/// Deserialize a duration in the same format as [`parse_duration`]
+fn deserialize_duration<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|duration| parse_duration(&duration).map_err(serde::de::Error::custom))
+        .transpose()
+}
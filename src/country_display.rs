@@ -5,42 +5,235 @@ use std::fmt::Write as _;
 
 use colored::Colorize as _;
 use docstr::docstr;
-use separator::Separatable as _;
 use simply_colored::*;
 
+use crate::countries::COUNTRIES_DATA;
 use crate::countries::Country;
 use crate::extra_country_data::CurrencyPosition;
+use crate::fields::Field;
+use crate::fields::Selection;
+use crate::numbers::NumberFormat;
+use crate::units::Units;
+
+/// Settings for how a country is displayed
+#[derive(Default)]
+pub struct Options {
+    /// Which fields to show
+    pub fields: Selection,
+    /// How population, area and density are written
+    pub numbers: NumberFormat,
+    /// Units of area, density and length
+    pub units: Units,
+    /// Replace the cca3 codes of neighbours with objects in JSON, YAML and TOML output
+    pub expand_borders: bool,
+    /// Width of the terminal, which decides where the flag goes.
+    /// When it is not known, the information is wrapped at 40 columns next to the flag
+    pub width: Option<usize>,
+    /// Moment at which the time in each timezone is shown, instead of now
+    pub at: Option<jiff::Timestamp>,
+    /// Language of the names of countries, such as `fra`. `None` is English
+    pub lang: Option<String>,
+}
+
+/// Displays a country according to [`Options`], created with [`Country::display`]
+pub struct CountryDisplay<'a> {
+    country: &'a Country,
+    options: &'a Options,
+}
+
+impl Country {
+    /// Display this country according to `options`
+    pub fn display<'a>(&'a self, options: &'a Options) -> CountryDisplay<'a> {
+        CountryDisplay {
+            country: self,
+            options,
+        }
+    }
+}
 
 impl fmt::Display for Country {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let area_km = self.area;
+        self.display(&Options::default()).fmt(f)
+    }
+}
+
+impl fmt::Display for CountryDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let country = self.country;
 
         // The new API doesn't provide a direct emoji flag, so we'll check if the
         // flag field (which is a string, often the emoji) exists. If not, it's None.
-        let flag_emoji: Option<&str> = self.flag.as_deref();
+        let flag_emoji: Option<&str> = country.flag.as_deref();
 
         // The new API provides flag_ascii_plain and flag_ascii_colored
-        let flag_ascii = &self.flag_ascii_colored;
-
-        let dialing_code = if let Some(idd) = &self.idd {
-            if let (Some(root), Some(suffixes)) = (&idd.root, &idd.suffixes) {
-                // Simplification: Join the root and the first suffix.
-                Some(format!(
-                    "{}{}",
-                    root,
-                    suffixes.first().unwrap_or(&"".to_string())
-                ))
-            } else {
-                None
+        let c_flag = &country.flag_ascii_colored;
+
+        let brightest_color = brightest_color(country);
+
+        let country_name = crate::names::name(country, self.options);
+        let flag_emoji = flag_emoji
+            .map(|flag| format!(" {flag}"))
+            .unwrap_or_default();
+
+        let separator = "─".repeat(textwrap::core::display_width(country_name));
+
+        let mut output = docstr!(format!
+            /// {country_name}{flag_emoji}
+            /// {brightest_color}{separator}{RESET}
+            ///
+        );
+
+        let fields = self.options.fields.fields();
+
+        for field in &fields {
+            let line = match field {
+                // Each neighbour is in the color of its own flag
+                Field::Neighbours => Some(neighbours_line(country, self.options, true)),
+                field => field_value(country, *field, self.options),
+            };
+
+            if let Some((label, value)) = line {
+                output.push_str(&format!("\n{brightest_color}{label}{RESET}: {value}"));
             }
-        } else {
-            None
-        };
+        }
+
+        if fields.contains(&Field::Palette) {
+            let palette = country
+                .flag_palette
+                .iter()
+                .fold(String::new(), |mut output, color| {
+                    let _ = write!(output, "{}", "███".truecolor(color.0, color.1, color.2));
+                    output
+                });
 
-        let currency_data = {
-            let position = crate::extra_country_data::currency_position(self.kind());
+            output.push_str(&format!("\n\n{palette}"));
+        }
+
+        f.write_str(&crate::layout::render(c_flag, &output, self.options.width))?;
+
+        Ok(())
+    }
+}
+
+/// Label and value of a field, such as `("Capital", "London")`
+///
+/// Returns `None` when the country does not have this information,
+/// and for fields which are not shown as a line of text.
+pub fn field_value(country: &Country, field: Field, options: &Options) -> Option<(String, String)> {
+    let plural = |count: usize, one: &str, many: &str| -> String {
+        if count == 1 { one } else { many }.to_string()
+    };
+
+    match field {
+        Field::Names => {
+            crate::names::native(country)
+                .map(|(names, count)| (plural(count, "Native name", "Native names"), names))
+        }
+        Field::Area => {
+            Some((
+                "Area".to_string(),
+                options.units.area(country.area, &options.numbers),
+            ))
+        }
+        Field::Density => {
+            crate::sort::density(country).map(|density| {
+                (
+                    "Density".to_string(),
+                    options.units.density(density, &options.numbers),
+                )
+            })
+        }
+        Field::SquareSide => {
+            (country.area > 0.0).then(|| {
+                (
+                    "Equivalent square".to_string(),
+                    format!(
+                        "{} per side",
+                        options.units.length(country.area.sqrt(), &options.numbers)
+                    ),
+                )
+            })
+        }
+        Field::Continents => {
+            Some((
+                plural(country.continents.len(), "Continent", "Continents"),
+                country.continents.join(", "),
+            ))
+        }
+        Field::Region => {
+            Some(("Region".to_string(), match &country.subregion {
+                Some(subregion) => format!("{} ({subregion})", country.region),
+                None => country.region.clone(),
+            }))
+        }
+        Field::Population => {
+            Some((
+                "Population".to_string(),
+                format!(
+                    "{} People",
+                    options.numbers.format(country.population as f64, 0)
+                ),
+            ))
+        }
+        Field::Neighbours => Some(neighbours_line(country, options, false)),
+        Field::Capital => {
+            country
+                .capital
+                .as_ref()
+                .filter(|capital| !capital.is_empty())
+                .map(|capital| {
+                    (
+                        plural(capital.len(), "Capital", "Capital Cities"),
+                        capital.join(", "),
+                    )
+                })
+        }
+        Field::Timezones => {
+            (!country.timezones.is_empty()).then(|| {
+                (
+                    plural(country.timezones.len(), "Timezone", "Timezones"),
+                    crate::timezones::describe(
+                        &country.timezones,
+                        options.at.unwrap_or_else(jiff::Timestamp::now),
+                        &jiff::tz::TimeZone::system(),
+                    ),
+                )
+            })
+        }
+        Field::IsoCodes => {
+            // Not every country has a numeric code, e.g. Kosovo
+            let codes = [&country.cca2, &country.cca3, &country.ccn3]
+                .into_iter()
+                .filter(|code| !code.is_empty())
+                .map(String::as_str)
+                .collect::<Vec<_>>();
+
+            Some(("ISO Codes".to_string(), codes.join(" / ")))
+        }
+        Field::DrivingSide => Some(("Driving side".to_string(), country.car.side.clone())),
+        Field::DialingCode => {
+            crate::dialing_code::format(country)
+                .map(|(codes, count)| (plural(count, "Dialing code", "Dialing codes"), codes))
+        }
+        Field::Languages => {
+            // Sorted by language code, as the order of a `HashMap` changes on every run
+            let mut languages = country.languages.iter().flatten().collect::<Vec<_>>();
+            languages.sort();
+            let languages = languages
+                .into_iter()
+                .map(|(_, language)| language.as_str())
+                .collect::<Vec<_>>();
+
+            Some((
+                plural(languages.len(), "Language", "Languages"),
+                languages.join(", "),
+            ))
+        }
+        Field::Currencies => {
+            let position = crate::extra_country_data::currency_position(country.kind());
 
-            let currencies: Vec<(String, String, String)> = self
+            // Sorted by currency code, as the order of a `HashMap` changes on every run
+            let mut currencies: Vec<(String, String, String)> = country
                 .currencies
                 .as_ref()
                 .map(|map| {
@@ -55,161 +248,99 @@ impl fmt::Display for Country {
                         .collect()
                 })
                 .unwrap_or_default();
+            currencies.sort();
+
+            let label = plural(currencies.len(), "Currency", "Currencies");
+
+            let currencies = match position {
+                CurrencyPosition::Left => {
+                    currencies
+                        .iter()
+                        .map(|(id, name, symbol)| format!("{symbol} {id} ({name})"))
+                        .collect::<Vec<_>>()
+                }
+                CurrencyPosition::Right => {
+                    currencies
+                        .iter()
+                        .map(|(id, name, symbol)| format!("{id} {symbol} ({name})"))
+                        .collect::<Vec<_>>()
+                }
+            };
+
+            Some((label, currencies.join(", ")))
+        }
+        Field::Tld => {
+            Some((
+                plural(country.tld.len(), "Top Level Domain", "Top Level Domains"),
+                country.tld.join(", "),
+            ))
+        }
+        Field::Palette => None,
+    }
+}
 
-            (position, currencies)
-        };
-
-        let brightest_color = brightest_color(self);
-
-        let c_flag = flag_ascii;
-        let c_flag_emoji = flag_emoji;
-        let c_area_km = area_km;
-        // rounds to the nearest 100
-        let c_area_mi = (area_km * (0.62137_f64.powi(2)) * 0.01).round() / 0.01;
-        let c_country_name = &self.name.common;
-
-        let c_continent = &self.continents;
-        let c_population = self.population;
-        let c_top_level_domain = &self.tld;
-
-        let c_languages: Vec<_> = self
-            .languages
-            .as_ref()
-            .map(|langs| langs.values().cloned().collect())
-            .unwrap_or_default();
-
-        let c_currency = currency_data;
-
-        let c_neighbours = &self.borders;
-
-        let c_iso_codes = (self.cca2.clone(), self.cca3.clone());
-
-        let c_driving_side = self.car.side.as_str();
-
-        let c_capital = self.capital.as_ref();
-        let c_dialing_code = dialing_code;
-
-        let c_palette = &self.flag_palette;
-
-        let km = format!("{:.0}", c_area_km.separated_string());
-        let mi = format!("{:.0}", c_area_mi.separated_string());
-
-        let country_name = c_country_name;
-        let flag_emoji = c_flag_emoji
-            .map(|flag| format!(" {flag}"))
-            .unwrap_or_default();
-        let continents_s = if c_continent.len() == 1 { "" } else { "s" };
-        let continents = c_continent.join(", ");
-        let people_count = c_population.separated_string();
-
-        let neigh = c_neighbours.join(", ");
-
-        let neigh_text = if neigh.is_empty() { "None" } else { &neigh };
-
-        let neighbours_s = if c_neighbours.len() == 1 { "" } else { "s" };
+/// Countries that share a border with `country`, along with their cca3 code.
+/// A code that is not in the data has no country
+pub fn neighbours(country: &Country) -> Vec<(&str, Option<&'static Country>)> {
+    country
+        .borders
+        .iter()
+        .map(|code| {
+            (
+                code.as_str(),
+                COUNTRIES_DATA
+                    .0
+                    .iter()
+                    .find(|neighbour| neighbour.cca3 == *code),
+            )
+        })
+        .collect()
+}
 
-        let capital =
-            c_capital
-                .filter(|capital| !capital.is_empty())
-                .map_or_else(String::new, |capital| {
+/// Label and value of [`Field::Neighbours`]. When `colored`, each neighbour
+/// is in the color of its own flag
+fn neighbours_line(country: &Country, options: &Options, colored: bool) -> (String, String) {
+    let label = if country.borders.len() == 1 {
+        "Neighbor"
+    } else {
+        "Neighbors"
+    };
+
+    let neighbours = neighbours(country)
+        .into_iter()
+        .map(|(code, neighbour)| {
+            match neighbour {
+                Some(neighbour) if colored => {
                     format!(
-                        "\n{brightest_color}Capital{}{RESET}: {}",
-                        if capital.len() == 1 { "" } else { " Cities" },
-                        capital.join(", ")
+                        "{}{}{RESET}",
+                        brightest_color(neighbour),
+                        neighbour_name(neighbour, options)
                     )
-                });
-
-        let iso_code_1 = &c_iso_codes.0;
-        let iso_code_2 = &c_iso_codes.1;
-        let driving_side = c_driving_side;
-
-        let dialing_code = c_dialing_code
-            .as_ref()
-            .map_or_else(String::new, |dialing_code| {
-                format!("\n{brightest_color}Dialing code{RESET}: {}", dialing_code)
-            });
-
-        let languages_s = if c_languages.len() == 1 { "" } else { "s" };
-        let languages = c_languages.join(", ");
-
-        let (currency_position, currencies) = &c_currency;
-        let currency_ies = if currencies.len() == 1 { "y" } else { "ies" };
-
-        let currencies = match currency_position {
-            CurrencyPosition::Left => {
-                currencies
-                    .iter()
-                    .map(|(id, name, symbol)| {
-                        format!("{symbol} {id} ({name})", symbol = symbol.as_str(),)
-                    })
-                    .collect::<Vec<_>>()
-            }
-            CurrencyPosition::Right => {
-                currencies
-                    .iter()
-                    .map(|(id, name, symbol)| {
-                        format!("{id} {symbol} ({name})", symbol = symbol.as_str())
-                    })
-                    .collect::<Vec<_>>()
+                }
+                Some(neighbour) => neighbour_name(neighbour, options),
+                None => code.to_string(),
             }
-        };
-        let currencies = currencies.join(", ");
-
-        let top_level_domain_suffix = if c_top_level_domain.len() == 1 {
-            ""
-        } else {
-            "s"
-        };
-        let top_level_domain = c_top_level_domain.join(", ");
-
-        let palette = c_palette.iter().fold(String::new(), |mut output, color| {
-            let _ = write!(output, "{}", "███".truecolor(color.0, color.1, color.2));
-            output
-        });
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
 
-        let separator = "─".repeat(country_name.len());
+    let neighbours = if neighbours.is_empty() {
+        "None".to_string()
+    } else {
+        neighbours
+    };
 
-        let output = docstr!(format!
-            /// {country_name}{flag_emoji}
-            /// {brightest_color}{separator}{RESET}
-            ///
-            /// {brightest_color}Area{RESET}: {km} km² ({mi} miles²)
-            /// {brightest_color}Continent{continents_s}{RESET}: {continents}
-            /// {brightest_color}Population{RESET}: {people_count} People
-            /// {brightest_color}Neighbor{neighbours_s}{RESET}: {neigh_text}{capital}
-            /// {brightest_color}ISO Codes{RESET}: {iso_code_1} / {iso_code_2}
-            /// {brightest_color}Driving side{RESET}: {driving_side}{dialing_code}
-            /// {brightest_color}Language{languages_s}{RESET}: {languages}
-            /// {brightest_color}Currenc{currency_ies}{RESET}: {currencies}
-            /// {brightest_color}Top Level Domain{top_level_domain_suffix}{RESET}: {top_level_domain}
-            ///
-            /// {palette}
-        );
-
-        let output = textwrap::wrap(&output, 40).join("\n");
-
-        let flag_line_len = self
-            .flag_ascii_plain
-            .lines()
-            .next()
-            .unwrap_or_default()
-            .len();
-
-        let lines = c_flag
-            .lines()
-            .map(|line| format!("  {line}"))
-            .chain(core::iter::repeat_with(|| {
-                format!("  {}", " ".repeat(flag_line_len))
-            }))
-            .zip(output.lines().chain(core::iter::repeat("")))
-            .take(c_flag.lines().count().max(output.lines().count()));
+    (label.to_string(), neighbours)
+}
 
-        for (flag_part, info_part) in lines {
-            let extra_space = if info_part.is_empty() { "" } else { "  " };
-            writeln!(f, "{flag_part}{extra_space}{info_part}")?;
-        }
+/// Flag and name of a neighbour, e.g. `🇫🇷 France`. They are joined with a
+/// non-breaking space, so that they stay on the same line
+fn neighbour_name(neighbour: &Country, options: &Options) -> String {
+    let name = crate::names::name(neighbour, options);
 
-        Ok(())
+    match &neighbour.flag {
+        Some(flag) => format!("{flag}\u{a0}{name}"),
+        None => name.to_string(),
     }
 }
 
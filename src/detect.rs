@@ -0,0 +1,424 @@
+//! Detects which country the user is in, when no country is passed explicitly
+
+use std::fmt;
+use std::fs;
+use std::net::IpAddr;
+use std::path::Path;
+
+use eyre::ContextCompat as _;
+use eyre::Result;
+use eyre::bail;
+use jiff::SignedDuration;
+use jiff::Timestamp;
+use jiff::tz::TimeZone;
+
+use crate::cache::Cache;
+use crate::countries::COUNTRIES_DATA;
+use crate::countries::Country;
+use crate::geoip;
+use crate::geoip_database;
+
+/// A way of finding out which country the user is in
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Strategy {
+    /// Look up the address of this machine in a local geo-IP database
+    Database,
+    /// Ask a geo-IP service over the network
+    Ip,
+    /// Use the region of the system locale, e.g. `GB` in `LANG=en_GB.UTF-8`
+    Locale,
+    /// Use the system timezone, from `TZ` or `/etc/localtime`
+    Timezone,
+}
+
+impl fmt::Display for Strategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Database => "database",
+            Self::Ip => "ip",
+            Self::Locale => "locale",
+            Self::Timezone => "timezone",
+        })
+    }
+}
+
+/// Strategies to try when the user does not choose any
+///
+/// When a local geo-IP database is available, it is used instead of the network.
+pub fn default_strategies(has_database: bool) -> Vec<Strategy> {
+    let first = if has_database {
+        Strategy::Database
+    } else {
+        Strategy::Ip
+    };
+
+    vec![first, Strategy::Locale, Strategy::Timezone]
+}
+
+/// Everything needed to detect the user's country
+pub struct Detector {
+    /// Strategies to try, in order
+    pub strategies: Vec<Strategy>,
+    /// Used by [`Strategy::Ip`]
+    pub provider: geoip::Provider,
+    /// Used by [`Strategy::Database`]
+    pub database: Option<geoip_database::Database>,
+    /// Remembers the country found by [`Strategy::Ip`]
+    pub cache: Option<Cache>,
+    /// Ask the geo-IP service even when the cached country is still fresh
+    pub refresh: bool,
+}
+
+/// Why a country could not be found
+#[derive(Debug)]
+pub enum DetectError {
+    /// The geo-IP service could not be reached, even after retrying
+    Request {
+        url: String,
+        attempts: u32,
+        source: ureq::Error,
+    },
+    /// The geo-IP service responded, but not with a country code
+    Response { url: String, body: String },
+    /// A country code was found, but no country has it
+    UnknownCountry { cca2: String },
+    /// Every strategy failed. The error of each one is kept, and is often a [`DetectError`] itself
+    AllFailed {
+        failures: Vec<(Strategy, eyre::Report)>,
+    },
+}
+
+impl fmt::Display for DetectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Request {
+                url,
+                attempts,
+                source,
+            } => {
+                let s = if *attempts == 1 { "" } else { "s" };
+                write!(
+                    f,
+                    "request to {url} failed after {attempts} attempt{s}: {source}"
+                )
+            }
+            Self::Response { url, body } => write!(f, "unexpected response from {url}: {body}"),
+            Self::UnknownCountry { cca2 } => write!(f, "no country with cca2 {cca2} found"),
+            Self::AllFailed { failures } => {
+                write!(
+                    f,
+                    "could not detect your country, pass one explicitly instead"
+                )?;
+
+                for (strategy, error) in failures {
+                    write!(f, "\n  {strategy}: {error}")?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for DetectError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Request { source, .. } => Some(source),
+            Self::Response { .. } | Self::UnknownCountry { .. } | Self::AllFailed { .. } => None,
+        }
+    }
+}
+
+/// The outcome of a successful detection
+pub struct Detection {
+    /// Country that was detected
+    pub country: &'static Country,
+    /// Strategy that found the country
+    pub strategy: Strategy,
+    /// Where exactly the country came from, e.g. `LANG=en_GB.UTF-8`
+    pub source: String,
+    /// Strategies that were tried before `strategy`, and why they failed
+    pub failures: Vec<(Strategy, eyre::Report)>,
+}
+
+impl Detection {
+    /// Human-readable explanation of how the country was found
+    pub fn report(&self) -> String {
+        let mut report = String::new();
+
+        for (strategy, error) in &self.failures {
+            report.push_str(&format!("{strategy}: failed: {error}\n"));
+        }
+
+        report.push_str(&format!(
+            "{}: detected {} from {}",
+            self.strategy, self.country.name.common, self.source
+        ));
+
+        report
+    }
+}
+
+impl Detector {
+    /// Try each strategy in order, returning the first country that was found
+    ///
+    /// When only one strategy was tried, its own [`DetectError`] is returned, such as
+    /// [`DetectError::UnknownCountry`]. Otherwise it is [`DetectError::AllFailed`].
+    pub fn detect(&self) -> Result<Detection, DetectError> {
+        let mut failures = Vec::new();
+
+        for &strategy in &self.strategies {
+            let result = match strategy {
+                Strategy::Database => from_database(self.database.as_ref()),
+                Strategy::Ip => self.ask_geoip_service(),
+                Strategy::Locale => from_locale(),
+                Strategy::Timezone => from_timezone(),
+            };
+
+            match result {
+                Ok((country, source)) => {
+                    return Ok(Detection {
+                        country,
+                        strategy,
+                        source,
+                        failures,
+                    });
+                }
+                Err(error) => failures.push((strategy, error)),
+            }
+        }
+
+        if failures.len() == 1
+            && let Some((strategy, error)) = failures.pop()
+        {
+            match error.downcast::<DetectError>() {
+                Ok(error) => return Err(error),
+                Err(error) => failures.push((strategy, error)),
+            }
+        }
+
+        Err(DetectError::AllFailed { failures })
+    }
+
+    /// Ask the geo-IP service where the user's IP address is located
+    ///
+    /// A cached country is used while it is fresh, and when the service cannot be reached.
+    /// It is not used when the service responds with something that is not a country code.
+    fn ask_geoip_service(&self) -> Result<(&'static Country, String)> {
+        let cached = self
+            .cache
+            .as_ref()
+            .and_then(|cache| cache.load(&self.provider.url));
+
+        if let (Some(cache), Some(cached)) = (&self.cache, &cached)
+            && !self.refresh
+            && !cached.is_stale(cache.ttl)
+        {
+            return Ok((country_from_cca2(&cached.cca2)?, cached.describe()));
+        }
+
+        match self.provider.lookup(None) {
+            Ok(cca2) => {
+                let country = country_from_cca2(&cca2)?;
+
+                if let Some(cache) = &self.cache {
+                    // Failing to cache the country should not prevent it from being shown
+                    let _ = cache.store(&self.provider.url, &cca2);
+                }
+
+                Ok((country, self.provider.url(None)))
+            }
+            // An unexpected response is a mistake in the configuration, which the cache would hide
+            Err(error @ DetectError::Request { .. }) => {
+                match cached {
+                    Some(cached) => {
+                        Ok((
+                            country_from_cca2(&cached.cca2)?,
+                            format!("{}, because the request failed: {error}", cached.describe()),
+                        ))
+                    }
+                    None => Err(error.into()),
+                }
+            }
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Find the country of an arbitrary address, preferring the local database over the network
+    ///
+    /// Returns the country, and where it came from.
+    pub fn locate(&self, ip: IpAddr) -> Result<(&'static Country, String)> {
+        match &self.database {
+            Some(database) => {
+                Ok((
+                    country_from_cca2(&database.lookup(ip)?)?,
+                    database.path.display().to_string(),
+                ))
+            }
+            None => {
+                Ok((
+                    country_from_cca2(&self.provider.lookup(Some(ip))?)?,
+                    self.provider.url(Some(ip)),
+                ))
+            }
+        }
+    }
+}
+
+/// Find the country with the given 2-letter code (cca2)
+fn country_from_cca2(cca2: &str) -> Result<&'static Country, DetectError> {
+    COUNTRIES_DATA
+        .0
+        .iter()
+        .find(|country| country.cca2.eq_ignore_ascii_case(cca2))
+        .ok_or_else(|| {
+            DetectError::UnknownCountry {
+                cca2: cca2.to_string(),
+            }
+        })
+}
+
+/// Look up the address of this machine in the local database
+fn from_database(
+    database: Option<&geoip_database::Database>,
+) -> Result<(&'static Country, String)> {
+    let database = database.wrap_err("no database was given with `--geoip-db`")?;
+
+    let ip = geoip_database::local_address()?;
+    let cca2 = database.lookup(ip)?;
+
+    Ok((
+        country_from_cca2(&cca2)?,
+        format!("{ip} in {}", database.path.display()),
+    ))
+}
+
+/// Read the region from the locale, e.g. `en_GB.UTF-8` or `pt-BR`
+fn from_locale() -> Result<(&'static Country, String)> {
+    let (var, locale) = ["LC_ALL", "LANG"]
+        .into_iter()
+        .find_map(|var| {
+            std::env::var(var)
+                .ok()
+                .filter(|value| !value.is_empty())
+                .map(|value| (var, value))
+        })
+        .wrap_err("neither `LC_ALL` nor `LANG` is set")?;
+
+    // Strip the encoding and modifier: `en_GB.UTF-8@euro` -> `en_GB`
+    let language_and_region = locale.split(['.', '@']).next().unwrap_or_default();
+
+    let region = language_and_region
+        .split(['_', '-'])
+        .nth(1)
+        .filter(|region| region.len() == 2 && region.chars().all(|c| c.is_ascii_alphabetic()))
+        .wrap_err_with(|| format!("locale `{var}={locale}` does not contain a country"))?;
+
+    Ok((country_from_cca2(region)?, format!("{var}={locale}")))
+}
+
+/// Find the country whose timezones contain the system timezone
+///
+/// The IANA name of the timezone, e.g. `Europe/Berlin`, is looked up in the
+/// system's `zone.tab`. When that is not available, the UTC offset of the timezone
+/// is matched against the `timezones` of each country instead.
+fn from_timezone() -> Result<(&'static Country, String)> {
+    let tz = TimeZone::system();
+
+    let source = match (std::env::var("TZ"), tz.iana_name()) {
+        (Ok(var), _) if !var.is_empty() => format!("TZ={var}"),
+        (_, Some(name)) => format!("the system timezone {name}"),
+        (_, None) => "the system timezone".to_string(),
+    };
+
+    let offset = standard_offset(&tz);
+
+    // Countries that use the same UTC offset as the system
+    let candidates = COUNTRIES_DATA
+        .0
+        .iter()
+        .filter(|country| country.timezones.contains(&offset))
+        .collect::<Vec<_>>();
+
+    if let Some(name) = tz.iana_name()
+        && let Some(codes) = zone_tab_countries(name)
+    {
+        // The zone can be shared by multiple countries, so prefer the ones with the right offset
+        let country = codes
+            .iter()
+            .filter_map(|cca2| country_from_cca2(cca2).ok())
+            .find(|country| {
+                candidates
+                    .iter()
+                    .any(|candidate| std::ptr::eq(*candidate, *country))
+            })
+            .or_else(|| codes.first().and_then(|cca2| country_from_cca2(cca2).ok()));
+
+        if let Some(country) = country {
+            return Ok((country, source));
+        }
+    }
+
+    match candidates.as_slice() {
+        [] => bail!("no country uses the timezone {offset}"),
+        [country] => Ok((*country, source)),
+        candidates => {
+            bail!(
+                "timezone {offset} is used by {} countries, cannot choose one",
+                candidates.len()
+            )
+        }
+    }
+}
+
+/// The UTC offset of the timezone when daylight saving time is not in effect,
+/// formatted like the `timezones` of [`Country`], e.g. `UTC+05:30` or just `UTC`
+fn standard_offset(tz: &TimeZone) -> String {
+    let now = Timestamp::now();
+
+    // Daylight saving time is never in effect at both moments half a year apart
+    let half_a_year_later = now
+        .checked_add(SignedDuration::from_hours(24 * 182))
+        .unwrap_or(now);
+
+    let offset = [now, half_a_year_later]
+        .into_iter()
+        .map(|timestamp| tz.to_offset_info(timestamp))
+        .find(|info| !info.dst().is_dst())
+        .unwrap_or_else(|| tz.to_offset_info(now))
+        .offset();
+
+    let seconds = offset.seconds();
+
+    if seconds == 0 {
+        "UTC".to_string()
+    } else {
+        let sign = if seconds < 0 { '-' } else { '+' };
+        let minutes = seconds.unsigned_abs() / 60;
+        format!("UTC{sign}{:02}:{:02}", minutes / 60, minutes % 60)
+    }
+}
+
+/// Look up the 2-letter codes of the countries that use the IANA timezone `name`
+///
+/// Both `zone.tab` and `zone1970.tab` consist of tab-separated lines
+/// where the first column is a comma-separated list of country codes,
+/// and the third column is the name of the timezone.
+fn zone_tab_countries(name: &str) -> Option<Vec<String>> {
+    let tzdir = std::env::var("TZDIR").unwrap_or_else(|_| "/usr/share/zoneinfo".to_string());
+
+    ["zone.tab", "zone1970.tab"].into_iter().find_map(|file| {
+        let contents = fs::read_to_string(Path::new(&tzdir).join(file)).ok()?;
+
+        contents
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .find_map(|line| {
+                let mut columns = line.split('\t');
+                let codes = columns.next()?;
+                let zone = columns.nth(1)?;
+
+                (zone == name).then(|| codes.split(',').map(str::to_string).collect())
+            })
+    })
+}
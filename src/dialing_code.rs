@@ -0,0 +1,127 @@
+//! International dialing codes of countries, and finding countries by their code with `--dialing-code`
+
+use core::fmt;
+use std::str::FromStr;
+
+use crate::countries::Country;
+
+/// Roots that are already a whole country code, shared by several countries.
+/// Their suffixes are area codes, e.g. `+1` and `876` for Jamaica
+const SHARED_ROOTS: [&str; 2] = ["+1", "+7"];
+
+/// With more area codes than this, only the shared root is shown
+const MAX_AREA_CODES: usize = 3;
+
+/// Every dialing code of the country, the root followed by each suffix, e.g. `+44`
+pub fn codes(country: &Country) -> Vec<String> {
+    let Some((root, suffixes)) = parts(country) else {
+        return Vec::new();
+    };
+
+    if suffixes.is_empty() {
+        return vec![root.to_string()];
+    }
+
+    suffixes
+        .iter()
+        .map(|suffix| format!("{root}{suffix}"))
+        .collect()
+}
+
+/// Dialing codes as shown to people, e.g. `+44`, `+1` for the United States,
+/// `+1 (787, 939)` for Puerto Rico or `+3906698, +379` for Vatican City.
+/// Also returns how many distinct country codes there are
+pub fn format(country: &Country) -> Option<(String, usize)> {
+    let (root, suffixes) = parts(country)?;
+
+    if !SHARED_ROOTS.contains(&root) {
+        let codes = codes(country);
+        let count = codes.len();
+        return Some((codes.join(", "), count));
+    }
+
+    if suffixes.is_empty() || suffixes.len() > MAX_AREA_CODES {
+        Some((root.to_string(), 1))
+    } else {
+        Some((format!("{root} ({})", suffixes.join(", ")), 1))
+    }
+}
+
+/// Countries whose dialing code starts `number`. When codes of different lengths
+/// match, only the longest is used: `+1 876 555 0100` is in Jamaica, not anywhere with `+1`
+pub fn find(number: &DialingCode) -> Vec<&'static Country> {
+    let mut longest = 0;
+    let mut found = Vec::new();
+
+    for country in crate::gen_countries::all_countries() {
+        let mut candidates = codes(country);
+
+        if let Some((root, _)) = parts(country)
+            && SHARED_ROOTS.contains(&root)
+        {
+            candidates.push(root.to_string());
+        }
+
+        let Some(length) = candidates
+            .iter()
+            .filter(|code| number.0.starts_with(code.as_str()))
+            .map(String::len)
+            .max()
+        else {
+            continue;
+        };
+
+        if length > longest {
+            longest = length;
+            found.clear();
+        }
+
+        if length == longest {
+            found.push(country);
+        }
+    }
+
+    found
+}
+
+/// Root and suffixes of the dialing code, `None` if the country has no dialing code
+fn parts(country: &Country) -> Option<(&str, &[String])> {
+    let idd = country.idd.as_ref()?;
+    let root = idd.root.as_deref().filter(|root| !root.is_empty())?;
+
+    Some((root, idd.suffixes.as_deref().unwrap_or_default()))
+}
+
+/// A dialing code or phone number such as `+44`, `0044` or `+1 (876) 555-0100`
+#[derive(Clone, Debug)]
+pub struct DialingCode(String);
+
+impl FromStr for DialingCode {
+    type Err = String;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let digits = source
+            .chars()
+            .filter(|c| !matches!(c, ' ' | '-' | '.' | '(' | ')'))
+            .collect::<String>();
+
+        let digits = digits
+            .strip_prefix('+')
+            .or_else(|| digits.strip_prefix("00"))
+            .unwrap_or(&digits);
+
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err(format!(
+                "expected a dialing code such as `+44`, found `{source}`"
+            ));
+        }
+
+        Ok(Self(format!("+{digits}")))
+    }
+}
+
+impl fmt::Display for DialingCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
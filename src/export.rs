@@ -0,0 +1,447 @@
+//! Output formats for countries other than the default, chosen with `--format`
+
+use std::io::Write;
+
+use colored_json::ColoredFormatter;
+use serde_json::Value;
+use serde_json::ser::CompactFormatter;
+
+use crate::countries::Country;
+use crate::country_display;
+use crate::country_display::Options;
+use crate::fields::Selection;
+use crate::sort;
+
+/// How countries are printed
+#[derive(clap::ValueEnum, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum Format {
+    /// Flag next to information about the country, meant for humans
+    #[default]
+    Pretty,
+    /// JSON object, or an array of them for multiple countries. Same as `--json`
+    Json,
+    /// Newline-delimited JSON, one compact object per line
+    Ndjson,
+    /// YAML mapping, or a sequence of them for multiple countries
+    Yaml,
+    /// TOML table. Multiple countries are in a `countries` array of tables
+    Toml,
+    /// Comma-separated values, one country per line
+    Csv,
+    /// Tab-separated values, one country per line
+    Tsv,
+    /// Fact sheet for wikis, with a table, links to maps and images of the flag and coat of arms
+    Markdown,
+    /// Same fact sheet as `markdown`, as an HTML `<article>` for each country
+    Html,
+}
+
+/// Separates multiple values inside of a single column, e.g. the languages of a country
+const LIST_SEPARATOR: &str = "; ";
+
+/// Dotted paths of the maps in the data. Their keys are sorted, as the order
+/// of a `HashMap` changes on every run
+const MAPS: &[&str] = &[
+    "name.nativeName",
+    "currencies",
+    "languages",
+    "translations",
+    "demonyms",
+    "gini",
+];
+
+/// A column of CSV and TSV output. The name is the dotted path of the value in the JSON output
+type Column = (&'static str, fn(&Country) -> String);
+
+/// Every column, in order. The header never depends on which countries are printed,
+/// so files exported at different times can be combined
+///
+/// Maps whose keys differ between countries, such as `currencies` and `languages`,
+/// are joined into a single column so that the header stays the same.
+const COLUMNS: &[Column] = &[
+    ("name.common", |country| country.name.common.clone()),
+    ("name.official", |country| country.name.official.clone()),
+    ("cca2", |country| country.cca2.clone()),
+    ("cca3", |country| country.cca3.clone()),
+    ("ccn3", |country| country.ccn3.clone()),
+    ("cioc", |country| optional(country.cioc.as_ref())),
+    ("fifa", |country| optional(country.fifa.as_ref())),
+    ("independent", |country| {
+        optional(country.independent.as_ref())
+    }),
+    ("status", |country| country.status.clone()),
+    ("unMember", |country| country.un_member.to_string()),
+    ("tld", |country| country.tld.join(LIST_SEPARATOR)),
+    ("currencies", |country| {
+        currencies(country, |code, _| code.to_string())
+    }),
+    ("currencies.name", |country| {
+        currencies(country, |_, currency| currency.name.clone())
+    }),
+    ("currencies.symbol", |country| {
+        currencies(country, |_, currency| {
+            currency.symbol.clone().unwrap_or_default()
+        })
+    }),
+    ("idd", |country| {
+        crate::dialing_code::codes(country).join(LIST_SEPARATOR)
+    }),
+    ("capital", |country| {
+        country
+            .capital
+            .as_deref()
+            .unwrap_or_default()
+            .join(LIST_SEPARATOR)
+    }),
+    ("capitalInfo.latlng", |country| {
+        list(
+            country
+                .capital_info
+                .as_ref()
+                .and_then(|capital_info| capital_info.latlng.as_deref())
+                .unwrap_or_default(),
+        )
+    }),
+    ("altSpellings", |country| {
+        country.alt_spellings.join(LIST_SEPARATOR)
+    }),
+    ("region", |country| country.region.clone()),
+    ("subregion", |country| optional(country.subregion.as_ref())),
+    ("continents", |country| {
+        country.continents.join(LIST_SEPARATOR)
+    }),
+    ("languages", |country| {
+        let mut languages = country.languages.iter().flatten().collect::<Vec<_>>();
+        languages.sort();
+        languages
+            .into_iter()
+            .map(|(_, language)| language.as_str())
+            .collect::<Vec<_>>()
+            .join(LIST_SEPARATOR)
+    }),
+    ("latlng", |country| list(&country.latlng)),
+    ("landlocked", |country| country.landlocked.to_string()),
+    ("borders", |country| country.borders.join(LIST_SEPARATOR)),
+    ("area", |country| country.area.to_string()),
+    ("population", |country| country.population.to_string()),
+    ("demonyms.eng.f", |country| {
+        demonym(country, "eng", |d| &d.f)
+    }),
+    ("demonyms.eng.m", |country| {
+        demonym(country, "eng", |d| &d.m)
+    }),
+    ("demonyms.fra.f", |country| {
+        demonym(country, "fra", |d| &d.f)
+    }),
+    ("demonyms.fra.m", |country| {
+        demonym(country, "fra", |d| &d.m)
+    }),
+    ("gini", |country| {
+        optional(sort::latest_gini(country).as_ref())
+    }),
+    ("gini.year", |country| {
+        country
+            .gini
+            .iter()
+            .flat_map(|gini| gini.keys())
+            .max()
+            .cloned()
+            .unwrap_or_default()
+    }),
+    ("car.side", |country| country.car.side.clone()),
+    ("car.signs", |country| {
+        country.car.signs.join(LIST_SEPARATOR)
+    }),
+    ("postalCode.format", |country| {
+        optional(
+            country
+                .postal_code
+                .as_ref()
+                .and_then(|postal_code| postal_code.format.as_ref()),
+        )
+    }),
+    ("postalCode.regex", |country| {
+        optional(
+            country
+                .postal_code
+                .as_ref()
+                .and_then(|postal_code| postal_code.regex.as_ref()),
+        )
+    }),
+    ("startOfWeek", |country| country.start_of_week.clone()),
+    ("timezones", |country| {
+        country.timezones.join(LIST_SEPARATOR)
+    }),
+    ("flag", |country| optional(country.flag.as_ref())),
+    ("flags.svg", |country| country.flags.svg.clone()),
+    ("flags.png", |country| country.flags.png.clone()),
+    ("maps.googleMaps", |country| {
+        country.maps.google_maps.clone()
+    }),
+    ("maps.openStreetMaps", |country| {
+        country.maps.open_street_maps.clone()
+    }),
+];
+
+/// Write the countries as pretty, colored JSON
+pub fn write_json(
+    writer: &mut impl Write,
+    countries: &[&Country],
+    options: &Options,
+    is_list: bool,
+) -> eyre::Result<()> {
+    let document = document(countries, options, is_list)?;
+    writeln!(writer, "{}", colored_json::to_colored_json_auto(&document)?)?;
+
+    Ok(())
+}
+
+/// Write each country as JSON on its own line, for tools that read one record per line
+pub fn write_ndjson(
+    writer: &mut impl Write,
+    countries: &[&Country],
+    options: &Options,
+) -> eyre::Result<()> {
+    for value in values(countries, options)? {
+        let line = ColoredFormatter::new(CompactFormatter).to_colored_json_auto(&value)?;
+        writeln!(writer, "{line}")?;
+    }
+
+    Ok(())
+}
+
+/// Write the countries as YAML
+pub fn write_yaml(
+    writer: &mut impl Write,
+    countries: &[&Country],
+    options: &Options,
+    is_list: bool,
+) -> eyre::Result<()> {
+    let document = document(countries, options, is_list)?;
+    write!(writer, "{}", serde_yaml_ng::to_string(&document)?)?;
+
+    Ok(())
+}
+
+/// Write the countries as TOML
+pub fn write_toml(
+    writer: &mut impl Write,
+    countries: &[&Country],
+    options: &Options,
+    is_list: bool,
+) -> eyre::Result<()> {
+    let mut document = document(countries, options, is_list)?;
+
+    // TOML has no null, missing values are left out instead
+    remove_nulls(&mut document);
+
+    // A TOML document is always a table, so a list of countries needs a key
+    if document.is_array() {
+        document = serde_json::json!({ "countries": document });
+    }
+
+    write!(writer, "{}", toml::to_string(&document)?)?;
+
+    Ok(())
+}
+
+/// Representation of each country that every serde format starts from,
+/// without the fields that are not shown
+fn values(countries: &[&Country], options: &Options) -> eyre::Result<Vec<Value>> {
+    countries
+        .iter()
+        .map(|country| {
+            let mut value = serde_json::to_value(country)?;
+            sort_maps(&mut value);
+            insert_dialing_codes(&mut value, country);
+
+            if options.expand_borders
+                && let Some(borders) = value.get_mut("borders")
+            {
+                *borders = expand_borders(country);
+            }
+
+            options.fields.filter_json(&mut value);
+            Ok(value)
+        })
+        .collect()
+}
+
+/// Sort the keys of each map in [`MAPS`], so the same country is always written the same way
+fn sort_maps(value: &mut Value) {
+    for path in MAPS {
+        let map = path
+            .split('.')
+            .try_fold(&mut *value, |value, key| value.get_mut(key));
+
+        if let Some(Value::Object(map)) = map {
+            map.sort_keys();
+        }
+    }
+}
+
+/// Add `dialingCodes`, every dialing code of the country, right after `idd`
+fn insert_dialing_codes(value: &mut Value, country: &Country) {
+    let Value::Object(map) = value else {
+        return;
+    };
+
+    let codes = Value::from(crate::dialing_code::codes(country));
+
+    *map = core::mem::take(map)
+        .into_iter()
+        .flat_map(|(key, value)| {
+            let dialing_codes = (key == "idd").then(|| ("dialingCodes".to_string(), codes.clone()));
+            core::iter::once((key, value)).chain(dialing_codes)
+        })
+        .collect();
+}
+
+/// Each neighbour as an object with its codes, name and flag, instead of just its cca3 code
+fn expand_borders(country: &Country) -> Value {
+    country_display::neighbours(country)
+        .into_iter()
+        .map(|(code, neighbour)| {
+            match neighbour {
+                Some(neighbour) => {
+                    serde_json::json!({
+                        "cca2": neighbour.cca2,
+                        "cca3": neighbour.cca3,
+                        "name": neighbour.name.common,
+                        "flag": neighbour.flag,
+                    })
+                }
+                None => serde_json::json!({ "cca3": code }),
+            }
+        })
+        .collect()
+}
+
+/// All countries as one document
+///
+/// A single country is a single object. When more countries could have been
+/// printed (`is_list`), it is an array even if it has just one country
+fn document(countries: &[&Country], options: &Options, is_list: bool) -> eyre::Result<Value> {
+    let mut values = values(countries, options)?;
+
+    Ok(match values.as_mut_slice() {
+        [value] if !is_list => value.take(),
+        _ => Value::Array(values),
+    })
+}
+
+/// Remove every `null` from objects, recursively
+fn remove_nulls(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            map.retain(|_, value| !value.is_null());
+            map.values_mut().for_each(remove_nulls);
+        }
+        Value::Array(values) => values.iter_mut().for_each(remove_nulls),
+        _ => (),
+    }
+}
+
+/// Write the countries as comma-separated values, quoted as described by RFC 4180
+pub fn write_csv(
+    writer: &mut impl Write,
+    countries: &[&Country],
+    selection: &Selection,
+) -> eyre::Result<()> {
+    write_delimited(writer, countries, selection, ',', |value| {
+        if value.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_string()
+        }
+    })
+}
+
+/// Write the countries as tab-separated values. Values cannot contain tabs or
+/// line breaks in this format, so they are replaced with spaces
+pub fn write_tsv(
+    writer: &mut impl Write,
+    countries: &[&Country],
+    selection: &Selection,
+) -> eyre::Result<()> {
+    write_delimited(writer, countries, selection, '\t', |value| {
+        value.replace(['\t', '\n', '\r'], " ")
+    })
+}
+
+/// Write a header, then one line for each country
+fn write_delimited(
+    writer: &mut impl Write,
+    countries: &[&Country],
+    selection: &Selection,
+    delimiter: char,
+    escape: impl Fn(&str) -> String,
+) -> eyre::Result<()> {
+    // `--fields` and `--hide` choose columns by the top-level key of the JSON output
+    let columns = COLUMNS
+        .iter()
+        .filter(|(name, _)| selection.shows_key(name.split('.').next().unwrap_or(name)))
+        .collect::<Vec<_>>();
+
+    let delimiter = delimiter.to_string();
+
+    let header = columns
+        .iter()
+        .map(|(name, _)| escape(name))
+        .collect::<Vec<_>>();
+    writeln!(writer, "{}", header.join(&delimiter))?;
+
+    for country in countries {
+        let row = columns
+            .iter()
+            .map(|(_, value)| escape(&value(country)))
+            .collect::<Vec<_>>();
+        writeln!(writer, "{}", row.join(&delimiter))?;
+    }
+
+    Ok(())
+}
+
+/// Value that might be missing, which is an empty column
+fn optional(value: Option<&impl ToString>) -> String {
+    value.map(ToString::to_string).unwrap_or_default()
+}
+
+/// Numbers joined into a single column
+fn list(numbers: &[f64]) -> String {
+    numbers
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(LIST_SEPARATOR)
+}
+
+/// Something about each currency of the country, sorted by currency code
+fn currencies(
+    country: &Country,
+    value: impl Fn(&str, &crate::countries::Currency) -> String,
+) -> String {
+    let mut currencies = country.currencies.iter().flatten().collect::<Vec<_>>();
+    currencies.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    currencies
+        .into_iter()
+        .map(|(code, currency)| value(code, currency))
+        .collect::<Vec<_>>()
+        .join(LIST_SEPARATOR)
+}
+
+/// What people of the country are called in `language`
+fn demonym(
+    country: &Country,
+    language: &str,
+    gender: impl Fn(&crate::countries::Demonym) -> &String,
+) -> String {
+    optional(
+        country
+            .demonyms
+            .as_ref()
+            .and_then(|demonyms| demonyms.get(language))
+            .map(gender),
+    )
+}
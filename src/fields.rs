@@ -0,0 +1,193 @@
+//! Choosing which information about a country is shown, with `--fields` and `--hide`
+
+/// A piece of information about a country
+#[derive(clap::ValueEnum, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Field {
+    /// Names in the languages of the country
+    Names,
+    /// Area in km² and miles², depending on `--units`
+    Area,
+    /// People per km² or mile²
+    Density,
+    /// Side of a square with the same area
+    SquareSide,
+    /// Continents that the country is on
+    Continents,
+    /// Region and subregion, e.g. Europe and Western Europe
+    Region,
+    /// Number of people living in the country
+    Population,
+    /// Countries that share a border with this one
+    Neighbours,
+    /// Capital cities
+    Capital,
+    /// UTC offsets, with the time there and the difference from your time
+    Timezones,
+    /// ISO 3166-1 codes: cca2, cca3 and ccn3
+    IsoCodes,
+    /// Which side of the road cars drive on
+    DrivingSide,
+    /// International dialing code
+    DialingCode,
+    /// Official languages
+    Languages,
+    /// Currencies, with their symbols
+    Currencies,
+    /// Top level domains
+    Tld,
+    /// Most common colors of the flag
+    Palette,
+}
+
+impl Field {
+    /// Fields in the order that they are shown by default
+    pub const DEFAULT: &[Self] = &[
+        Self::Names,
+        Self::Area,
+        Self::Continents,
+        Self::Population,
+        Self::Density,
+        Self::Neighbours,
+        Self::Capital,
+        Self::Timezones,
+        Self::IsoCodes,
+        Self::DrivingSide,
+        Self::DialingCode,
+        Self::Languages,
+        Self::Currencies,
+        Self::Tld,
+        Self::Palette,
+    ];
+
+    /// Columns of `--table`, in the order that they are shown by default
+    pub const TABLE: &[Self] = &[
+        Self::IsoCodes,
+        Self::Capital,
+        Self::Region,
+        Self::Population,
+        Self::Area,
+    ];
+
+    /// Keys of the JSON representation of a `Country` that hold this field.
+    /// A key can be a dotted path, such as `name.nativeName`
+    pub fn json_keys(self) -> &'static [&'static str] {
+        match self {
+            Self::Names => &["name.nativeName"],
+            Self::Area => &["area"],
+            // Computed from the area and population, so not in the JSON
+            Self::Density | Self::SquareSide => &[],
+            Self::Continents => &["continents"],
+            Self::Region => &["region", "subregion"],
+            Self::Population => &["population"],
+            Self::Neighbours => &["borders"],
+            Self::Capital => &["capital", "capitalInfo"],
+            Self::Timezones => &["timezones"],
+            Self::IsoCodes => &["cca2", "cca3", "ccn3"],
+            Self::DrivingSide => &["car"],
+            Self::DialingCode => &["idd", "dialingCodes"],
+            Self::Languages => &["languages"],
+            Self::Currencies => &["currencies"],
+            Self::Tld => &["tld"],
+            Self::Palette => &["flagPalette"],
+        }
+    }
+}
+
+/// Which fields to show, and in what order
+#[derive(Default, Clone, Debug)]
+pub struct Selection {
+    /// Only show these fields, in this order. `None` shows the default fields
+    pub only: Option<Vec<Field>>,
+    /// Never show these fields
+    pub hidden: Vec<Field>,
+}
+
+impl Selection {
+    /// Fields that should be shown, in order
+    pub fn fields(&self) -> Vec<Field> {
+        self.fields_or(Field::DEFAULT)
+    }
+
+    /// Fields that should be shown, in order, when `default` is shown without `--fields`
+    pub fn fields_or(&self, default: &[Field]) -> Vec<Field> {
+        self.only
+            .as_deref()
+            .unwrap_or(default)
+            .iter()
+            .copied()
+            .filter(|field| !self.hidden.contains(field))
+            .collect()
+    }
+
+    /// Whether `key` of the JSON representation of a `Country` is shown, following
+    /// the same rules as [`Selection::filter_json`]
+    pub fn shows_key(&self, key: &str) -> bool {
+        let is_key_of = |field: &Field| field.json_keys().contains(&key);
+
+        match &self.only {
+            None => !self.hidden.iter().any(is_key_of),
+            Some(_) => key == "name" || self.fields().iter().any(is_key_of),
+        }
+    }
+
+    /// Remove the keys of fields that are not shown from the JSON representation of a `Country`
+    ///
+    /// Without `--fields`, everything except the hidden fields is kept. With it, only the
+    /// country's name and the selected fields remain.
+    pub fn filter_json(&self, country: &mut serde_json::Value) {
+        let Some(country) = country.as_object_mut() else {
+            return;
+        };
+
+        match &self.only {
+            None => {
+                for key in self.hidden.iter().flat_map(|field| field.json_keys()) {
+                    remove_key(country, key);
+                }
+            }
+            Some(_) => {
+                let fields = self.fields();
+                let keys = fields.iter().flat_map(|field| field.json_keys());
+
+                let mut filtered = serde_json::Map::new();
+
+                for key in std::iter::once(&"name").chain(keys) {
+                    if let Some(value) = country.shift_remove(*key) {
+                        filtered.insert(key.to_string(), value);
+                    }
+                }
+
+                // `name` is kept as a whole, so the keys inside of it that belong
+                // to fields which are not selected are removed separately
+                let unselected = <Field as clap::ValueEnum>::value_variants()
+                    .iter()
+                    .filter(|field| !fields.contains(field));
+
+                for key in unselected.flat_map(|field| field.json_keys()) {
+                    if key.contains('.') {
+                        remove_key(&mut filtered, key);
+                    }
+                }
+
+                *country = filtered;
+            }
+        }
+    }
+}
+
+/// Remove `key` from the JSON representation of a `Country`. It can be a dotted path
+fn remove_key(country: &mut serde_json::Map<String, serde_json::Value>, key: &str) {
+    match key.split_once('.') {
+        Some((parent, key)) => {
+            if let Some(parent) = country
+                .get_mut(parent)
+                .and_then(|parent| parent.as_object_mut())
+            {
+                parent.shift_remove(key);
+            }
+        }
+        None => {
+            country.shift_remove(key);
+        }
+    }
+}
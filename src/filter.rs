@@ -0,0 +1,459 @@
+//! Filter expressions for `--where`, such as `region == "Europe" && population > 5000000`
+//!
+//! Grammar, from lowest to highest precedence:
+//!
+//! ```text
+//! or         = and ("||" and)*
+//! and        = not ("&&" not)*
+//! not        = "!" not | comparison
+//! comparison = value (("==" | "!=" | "<" | "<=" | ">" | ">=" | "in") value)?
+//! value      = "(" or ")" | string | number | "true" | "false" | "null" | path
+//! ```
+//!
+//! A path such as `name.common` or `languages` refers to a field of the country,
+//! with the same rules as `--get`.
+
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use serde_json::Value;
+
+use crate::countries::Country;
+use crate::query;
+
+/// A parsed `--where` expression
+#[derive(Clone, Debug)]
+pub struct Filter(Expr);
+
+impl Filter {
+    /// Whether `country` passes the filter
+    pub fn matches(&self, country: &Country) -> bool {
+        let Ok(country) = serde_json::to_value(country) else {
+            return false;
+        };
+
+        self.matches_value(&country)
+    }
+
+    /// Whether the JSON representation of a country passes the filter
+    pub fn matches_value(&self, country: &Value) -> bool {
+        is_truthy(&self.0.eval(country))
+    }
+}
+
+impl FromStr for Filter {
+    type Err = String;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+        };
+
+        let expr = parser.or()?;
+
+        match parser.tokens.get(parser.position) {
+            None => Ok(Self(expr)),
+            Some(token) => Err(format!("unexpected {}", token.describe())),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    String(String),
+    Number(f64),
+    Ident(String),
+    And,
+    Or,
+    Not,
+    Op(Op),
+    LeftParen,
+    RightParen,
+}
+
+impl Token {
+    /// For error messages
+    fn describe(&self) -> String {
+        match self {
+            Self::String(string) => format!("string {string:?}"),
+            Self::Number(number) => format!("number {number}"),
+            Self::Ident(ident) => format!("`{ident}`"),
+            Self::And => "`&&`".to_string(),
+            Self::Or => "`||`".to_string(),
+            Self::Not => "`!`".to_string(),
+            Self::Op(op) => format!("`{}`", op.as_str()),
+            Self::LeftParen => "`(`".to_string(),
+            Self::RightParen => "`)`".to_string(),
+        }
+    }
+}
+
+/// Operator that compares two values
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    In,
+}
+
+impl Op {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Eq => "==",
+            Self::Ne => "!=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+            Self::In => "in",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Expr {
+    Literal(Value),
+    Path(String),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Compare(Box<Expr>, Op, Box<Expr>),
+}
+
+impl Expr {
+    fn eval(&self, country: &Value) -> Value {
+        match self {
+            Self::Literal(value) => value.clone(),
+            Self::Path(path) => query::get(country, path).cloned().unwrap_or(Value::Null),
+            Self::Not(expr) => Value::Bool(!is_truthy(&expr.eval(country))),
+            Self::And(left, right) => {
+                Value::Bool(is_truthy(&left.eval(country)) && is_truthy(&right.eval(country)))
+            }
+            Self::Or(left, right) => {
+                Value::Bool(is_truthy(&left.eval(country)) || is_truthy(&right.eval(country)))
+            }
+            Self::Compare(left, op, right) => {
+                Value::Bool(compare(&left.eval(country), *op, &right.eval(country)))
+            }
+        }
+    }
+}
+
+/// Whether a value counts as `true` on its own, e.g. in `landlocked && capital`
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(bool) => *bool,
+        Value::Number(number) => number.as_f64().is_some_and(|number| number != 0.0),
+        Value::String(string) => !string.is_empty(),
+        Value::Array(values) => !values.is_empty(),
+        Value::Object(map) => !map.is_empty(),
+    }
+}
+
+fn compare(left: &Value, op: Op, right: &Value) -> bool {
+    match op {
+        Op::Eq => left == right || ordering(left, right) == Some(Ordering::Equal),
+        Op::Ne => !compare(left, Op::Eq, right),
+        Op::Lt => ordering(left, right) == Some(Ordering::Less),
+        Op::Le => {
+            matches!(
+                ordering(left, right),
+                Some(Ordering::Less | Ordering::Equal)
+            )
+        }
+        Op::Gt => ordering(left, right) == Some(Ordering::Greater),
+        Op::Ge => {
+            matches!(
+                ordering(left, right),
+                Some(Ordering::Greater | Ordering::Equal)
+            )
+        }
+        // `"eng" in languages` checks the keys of objects, but also their values
+        // so that `"English" in languages` works too
+        Op::In => {
+            match right {
+                Value::Array(values) => values.iter().any(|value| compare(left, Op::Eq, value)),
+                Value::Object(map) => {
+                    map.iter().any(|(key, value)| {
+                        left.as_str() == Some(key.as_str()) || compare(left, Op::Eq, value)
+                    })
+                }
+                Value::String(string) => left.as_str().is_some_and(|left| string.contains(left)),
+                _ => false,
+            }
+        }
+    }
+}
+
+/// Numbers compare with numbers, and strings with strings
+fn ordering(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::Number(left), Value::Number(right)) => left.as_f64()?.partial_cmp(&right.as_f64()?),
+        (Value::String(left), Value::String(right)) => Some(left.cmp(right)),
+        _ => None,
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::LeftParen,
+            ')' => Token::RightParen,
+            '&' if chars.next_if(|(_, c)| *c == '&').is_some() => Token::And,
+            '|' if chars.next_if(|(_, c)| *c == '|').is_some() => Token::Or,
+            '=' if chars.next_if(|(_, c)| *c == '=').is_some() => Token::Op(Op::Eq),
+            '!' if chars.next_if(|(_, c)| *c == '=').is_some() => Token::Op(Op::Ne),
+            '!' => Token::Not,
+            '<' if chars.next_if(|(_, c)| *c == '=').is_some() => Token::Op(Op::Le),
+            '<' => Token::Op(Op::Lt),
+            '>' if chars.next_if(|(_, c)| *c == '=').is_some() => Token::Op(Op::Ge),
+            '>' => Token::Op(Op::Gt),
+            quote @ ('"' | '\'') => {
+                let mut string = String::new();
+
+                loop {
+                    match chars.next() {
+                        Some((_, c)) if c == quote => break,
+                        Some((_, '\\')) => {
+                            match chars.next() {
+                                Some((_, c)) => string.push(c),
+                                None => return Err("unterminated string".to_string()),
+                            }
+                        }
+                        Some((_, c)) => string.push(c),
+                        None => return Err("unterminated string".to_string()),
+                    }
+                }
+
+                Token::String(string)
+            }
+            c if c.is_ascii_digit() || c == '-' => {
+                let mut end = start + c.len_utf8();
+                while let Some((i, c)) =
+                    chars.next_if(|(_, c)| c.is_ascii_digit() || matches!(c, '.' | '_'))
+                {
+                    end = i + c.len_utf8();
+                }
+
+                let number = source[start..end].replace('_', "");
+                Token::Number(
+                    number
+                        .parse()
+                        .map_err(|_| format!("invalid number `{number}`"))?,
+                )
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut end = start + c.len_utf8();
+                while let Some((i, c)) =
+                    chars.next_if(|(_, c)| c.is_alphanumeric() || matches!(c, '_' | '.'))
+                {
+                    end = i + c.len_utf8();
+                }
+
+                match &source[start..end] {
+                    "in" => Token::Op(Op::In),
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "not" => Token::Not,
+                    ident => Token::Ident(ident.to_string()),
+                }
+            }
+            c => return Err(format!("unexpected `{c}` at column {}", start + 1)),
+        };
+
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    /// Consume the next token if it is `token`
+    fn eat(&mut self, token: &Token) -> bool {
+        let matches = self.tokens.get(self.position) == Some(token);
+        if matches {
+            self.position += 1;
+        }
+        matches
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.eat(&Token::Or) {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.not()?;
+        while self.eat(&Token::And) {
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+        Ok(expr)
+    }
+
+    fn not(&mut self) -> Result<Expr, String> {
+        if self.eat(&Token::Not) {
+            Ok(Expr::Not(Box::new(self.not()?)))
+        } else {
+            self.comparison()
+        }
+    }
+
+    fn comparison(&mut self) -> Result<Expr, String> {
+        let left = self.value()?;
+
+        match self.tokens.get(self.position) {
+            Some(Token::Op(op)) => {
+                let op = *op;
+                self.position += 1;
+                Ok(Expr::Compare(Box::new(left), op, Box::new(self.value()?)))
+            }
+            _ => Ok(left),
+        }
+    }
+
+    fn value(&mut self) -> Result<Expr, String> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .cloned()
+            .ok_or("unexpected end of expression")?;
+        self.position += 1;
+
+        Ok(match token {
+            Token::LeftParen => {
+                let expr = self.or()?;
+                if !self.eat(&Token::RightParen) {
+                    return Err("expected `)`".to_string());
+                }
+                expr
+            }
+            Token::String(string) => Expr::Literal(Value::String(string)),
+            Token::Number(number) => {
+                Expr::Literal(
+                    serde_json::Number::from_f64(number).map_or(Value::Null, Value::Number),
+                )
+            }
+            Token::Ident(ident) => {
+                match ident.as_str() {
+                    "true" => Expr::Literal(Value::Bool(true)),
+                    "false" => Expr::Literal(Value::Bool(false)),
+                    "null" => Expr::Literal(Value::Null),
+                    _ => Expr::Path(ident),
+                }
+            }
+            token => return Err(format!("unexpected {}", token.describe())),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn country() -> Value {
+        json!({
+            "name": { "common": "Switzerland" },
+            "region": "Europe",
+            "population": 8_654_622,
+            "landlocked": true,
+            "languages": { "deu": "German", "fra": "French" },
+            "tld": [".ch"],
+            "capital": [],
+        })
+    }
+
+    fn matches(source: &str) -> bool {
+        source
+            .parse::<Filter>()
+            .unwrap_or_else(|error| panic!("failed to parse `{source}`: {error}"))
+            .matches_value(&country())
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        // `true || (false && false)`, not `(true || false) && false`
+        assert!(matches("landlocked || false && false"));
+        assert!(matches("false && false || landlocked"));
+        assert!(!matches("(landlocked || false) && false"));
+    }
+
+    #[test]
+    fn not_binds_tighter_than_and() {
+        assert!(!matches("!landlocked && true"));
+        assert!(matches("!(landlocked && false)"));
+        assert!(matches("not not landlocked"));
+    }
+
+    #[test]
+    fn comparisons() {
+        assert!(matches(r#"region == "Europe""#));
+        assert!(matches("region != 'Asia'"));
+        assert!(matches("population > 5_000_000"));
+        assert!(matches("population >= 8654622 and population <= 8654622"));
+        assert!(!matches("population < 100"));
+        assert!(matches(r#"name.common == "Switzerland""#));
+        assert!(matches("missing.path == null"));
+    }
+
+    #[test]
+    fn in_checks_keys_and_values_of_objects() {
+        assert!(matches(r#""deu" in languages"#));
+        assert!(matches(r#""French" in languages"#));
+        assert!(!matches(r#""eng" in languages"#));
+    }
+
+    #[test]
+    fn in_checks_items_of_arrays_and_substrings() {
+        assert!(matches(r#"".ch" in tld"#));
+        assert!(!matches(r#"".de" in tld"#));
+        assert!(matches(r#""Euro" in region"#));
+    }
+
+    #[test]
+    fn truthiness() {
+        assert!(matches("languages"));
+        assert!(!matches("capital"));
+        assert!(!matches("missing"));
+    }
+
+    #[test]
+    fn escapes_in_strings() {
+        let filter = r#"name.common == "Switz\"erland""#.parse::<Filter>().unwrap();
+        assert!(!filter.matches_value(&country()));
+        assert!(filter.matches_value(&json!({ "name": { "common": "Switz\"erland" } })));
+    }
+
+    #[test]
+    fn errors() {
+        let error = |source: &str| source.parse::<Filter>().unwrap_err();
+
+        assert_eq!(error(r#"region == "Europe"#), "unterminated string");
+        assert_eq!(error(r#"region == "Europe\"#), "unterminated string");
+        assert_eq!(error("(landlocked"), "expected `)`");
+        assert_eq!(error("landlocked)"), "unexpected `)`");
+        assert_eq!(error("population >"), "unexpected end of expression");
+        assert_eq!(error("region = 1"), "unexpected `=` at column 8");
+        assert_eq!(error("landlocked &&"), "unexpected end of expression");
+    }
+}
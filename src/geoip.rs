@@ -0,0 +1,337 @@
+//! Looks up the country of an IP address using a geo-IP web service
+
+use std::net::IpAddr;
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::detect::DetectError;
+
+/// Service used when the user does not configure one
+///
+/// `{ip}` is replaced by the address to look up. For the user's own address it is
+/// removed, together with the `/` in front of it.
+pub const DEFAULT_URL: &str = "https://api.country.is/{ip}";
+
+/// Shape of the response returned by a geo-IP service
+#[derive(clap::ValueEnum, Deserialize, Clone, Copy, Default, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum Schema {
+    /// Guess from the response, trying each of the other schemas
+    #[default]
+    Auto,
+    /// Plain text containing just the 2-letter country code
+    Text,
+    /// JSON object with a `country` field, like `api.country.is`
+    Country,
+    /// JSON object with a `country_code` field, like `ipapi.co`
+    CountryCode,
+    /// JSON object like the ones from `ipinfo.io`, where `country_code` is preferred over `country`
+    Ipinfo,
+}
+
+/// A geo-IP web service
+pub struct Provider {
+    /// Where to send the request
+    pub url: String,
+    /// How to read the response
+    pub schema: Schema,
+    /// Timeouts and retries of the request
+    pub network: Network,
+}
+
+/// How patient to be with the geo-IP service
+///
+/// Proxies are read from `ALL_PROXY`, `HTTPS_PROXY` or `HTTP_PROXY`,
+/// and hosts listed in `NO_PROXY` are connected to directly.
+pub struct Network {
+    /// Give up on connecting to the service after this long
+    pub connect_timeout: Duration,
+    /// Give up on waiting for the response after this long
+    pub read_timeout: Duration,
+    /// How many more times to send the request, when it fails
+    pub retries: u32,
+}
+
+impl Network {
+    pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+    pub const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(5);
+    pub const DEFAULT_RETRIES: u32 = 2;
+
+    /// Wait before the first retry, doubled for each retry after it
+    const BACKOFF: Duration = Duration::from_millis(250);
+
+    /// The wait between retries never gets longer than this, however many there are
+    const MAX_BACKOFF: Duration = Duration::from_secs(5);
+
+    /// How long to wait before sending the request again, after `attempts` have failed
+    fn backoff(attempts: u32) -> Duration {
+        Self::BACKOFF
+            .saturating_mul(2_u32.saturating_pow(attempts - 1))
+            .min(Self::MAX_BACKOFF)
+    }
+}
+
+impl Provider {
+    /// Ask the service for the 2-letter country code (cca2) of `ip`,
+    /// or of the user's own address when `ip` is `None`
+    pub fn lookup(&self, ip: Option<IpAddr>) -> Result<String, DetectError> {
+        let url = self.url(ip);
+
+        let agent: ureq::Agent = ureq::Agent::config_builder()
+            .timeout_connect(Some(self.network.connect_timeout))
+            .timeout_recv_response(Some(self.network.read_timeout))
+            .timeout_recv_body(Some(self.network.read_timeout))
+            .proxy(ureq::Proxy::try_from_env())
+            .user_agent("countryfetch")
+            .build()
+            .into();
+
+        let mut attempts = 0;
+
+        let body = loop {
+            attempts += 1;
+
+            let response = agent
+                .get(&url)
+                .call()
+                .and_then(|mut response| response.body_mut().read_to_string());
+
+            match response {
+                Ok(body) => break body,
+                Err(error) if attempts <= self.network.retries && is_transient(&error) => {
+                    std::thread::sleep(Network::backoff(attempts));
+                }
+                Err(source) => {
+                    return Err(DetectError::Request {
+                        url,
+                        attempts,
+                        source,
+                    });
+                }
+            }
+        };
+
+        parse(self.schema, &body).ok_or_else(|| {
+            DetectError::Response {
+                url,
+                body: body.trim().to_string(),
+            }
+        })
+    }
+
+    /// The URL to request for looking up `ip`
+    ///
+    /// When the URL has no `{ip}` placeholder, the address is added as the last path segment.
+    pub fn url(&self, ip: Option<IpAddr>) -> String {
+        match ip {
+            Some(ip) if self.url.contains("{ip}") => self.url.replace("{ip}", &ip.to_string()),
+            Some(ip) => format!("{}/{ip}", self.url.trim_end_matches('/')),
+            None => self.url.replace("/{ip}", "").replace("{ip}", ""),
+        }
+    }
+}
+
+/// Extract the 2-letter country code from the body of a response
+fn parse(schema: Schema, body: &str) -> Option<String> {
+    /// Every field that a JSON response could store the country code in
+    #[derive(Deserialize)]
+    struct Response {
+        country: Option<String>,
+        #[serde(alias = "countryCode")]
+        country_code: Option<String>,
+    }
+
+    let json = || serde_json::from_str::<Response>(body).ok();
+
+    let cca2 = match schema {
+        Schema::Auto => {
+            json()
+                .and_then(|response| {
+                    [response.country_code, response.country]
+                        .into_iter()
+                        .flatten()
+                        .find(|code| is_cca2(code))
+                })
+                .or_else(|| Some(body.trim().to_string()))
+        }
+        Schema::Text => Some(body.trim().to_string()),
+        Schema::Country => json()?.country,
+        Schema::CountryCode => json()?.country_code,
+        Schema::Ipinfo => {
+            json().and_then(|response| {
+                response
+                    .country_code
+                    .or(response.country.filter(|code| is_cca2(code)))
+            })
+        }
+    }?;
+
+    is_cca2(&cca2).then_some(cca2)
+}
+
+/// Whether sending the same request again could succeed
+fn is_transient(error: &ureq::Error) -> bool {
+    match error {
+        ureq::Error::StatusCode(status) => *status == 429 || *status >= 500,
+        ureq::Error::Io(_)
+        | ureq::Error::Timeout(_)
+        | ureq::Error::HostNotFound
+        | ureq::Error::ConnectionFailed
+        | ureq::Error::ConnectProxyFailed(_)
+        | ureq::Error::BodyStalled => true,
+        _ => false,
+    }
+}
+
+/// Whether `code` looks like a 2-letter country code, e.g. `GB`
+fn is_cca2(code: &str) -> bool {
+    code.len() == 2 && code.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufRead as _;
+    use std::io::BufReader;
+    use std::io::Write as _;
+    use std::net::TcpListener;
+
+    use super::*;
+
+    fn provider(url: &str, schema: Schema) -> Provider {
+        Provider {
+            url: url.to_string(),
+            schema,
+            network: Network {
+                connect_timeout: Duration::from_secs(1),
+                read_timeout: Duration::from_secs(1),
+                retries: 0,
+            },
+        }
+    }
+
+    #[test]
+    fn schemas() {
+        assert_eq!(parse(Schema::Text, "GB\n").as_deref(), Some("GB"));
+        assert_eq!(parse(Schema::Text, "Britain"), None);
+
+        assert_eq!(
+            parse(Schema::Country, r#"{"ip":"1.1.1.1","country":"AU"}"#).as_deref(),
+            Some("AU")
+        );
+        assert_eq!(parse(Schema::Country, r#"{"country_code":"AU"}"#), None);
+
+        assert_eq!(
+            parse(Schema::CountryCode, r#"{"country_code":"JP"}"#).as_deref(),
+            Some("JP")
+        );
+        assert_eq!(
+            parse(Schema::CountryCode, r#"{"countryCode":"JP"}"#).as_deref(),
+            Some("JP")
+        );
+        assert_eq!(parse(Schema::CountryCode, r#"{"country":"JP"}"#), None);
+
+        assert_eq!(
+            parse(
+                Schema::Ipinfo,
+                r#"{"country":"Germany","country_code":"DE"}"#
+            )
+            .as_deref(),
+            Some("DE")
+        );
+        assert_eq!(
+            parse(Schema::Ipinfo, r#"{"country":"DE"}"#).as_deref(),
+            Some("DE")
+        );
+        assert_eq!(parse(Schema::Ipinfo, r#"{"country":"Germany"}"#), None);
+    }
+
+    #[test]
+    fn auto_schema() {
+        let parse = |body| parse(Schema::Auto, body);
+
+        assert_eq!(parse("FR").as_deref(), Some("FR"));
+        assert_eq!(parse(r#"{"country":"FR"}"#).as_deref(), Some("FR"));
+        assert_eq!(parse(r#"{"country_code":"FR"}"#).as_deref(), Some("FR"));
+        assert_eq!(
+            parse(r#"{"country":"France","countryCode":"FR"}"#).as_deref(),
+            Some("FR")
+        );
+        assert_eq!(parse(r#"{"country":"France"}"#), None);
+        assert_eq!(parse("<html></html>"), None);
+    }
+
+    #[test]
+    fn url() {
+        let ip = Some("203.0.113.7".parse().unwrap());
+
+        let placeholder = provider("https://example.com/{ip}/json", Schema::Auto);
+        assert_eq!(placeholder.url(ip), "https://example.com/203.0.113.7/json");
+        assert_eq!(placeholder.url(None), "https://example.com/json");
+
+        let query = provider("https://example.com/lookup?ip={ip}", Schema::Auto);
+        assert_eq!(query.url(ip), "https://example.com/lookup?ip=203.0.113.7");
+        assert_eq!(query.url(None), "https://example.com/lookup?ip=");
+
+        let bare = provider("https://example.com/", Schema::Auto);
+        assert_eq!(bare.url(ip), "https://example.com/203.0.113.7");
+        assert_eq!(bare.url(None), "https://example.com/");
+    }
+
+    /// Answer a single request with `body`, returning the URL of the server
+    /// and the request line that it received
+    fn serve(body: &'static str) -> (String, std::thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+
+            // The rest of the headers, up to the empty line
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+
+            write!(
+                reader.get_mut(),
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+            .unwrap();
+
+            request_line.trim_end().to_string()
+        });
+
+        (url, server)
+    }
+
+    #[test]
+    fn lookup() {
+        let (url, server) = serve(r#"{"ip":"203.0.113.7","country":"NZ"}"#);
+        let provider = provider(&format!("{url}/{{ip}}"), Schema::Country);
+
+        let cca2 = provider.lookup(Some("203.0.113.7".parse().unwrap()));
+
+        assert_eq!(server.join().unwrap(), "GET /203.0.113.7 HTTP/1.1");
+        assert_eq!(cca2.unwrap(), "NZ");
+    }
+
+    #[test]
+    fn lookup_unexpected_response() {
+        let (url, server) = serve("<html></html>");
+        let provider = provider(&url, Schema::Text);
+
+        let error = provider.lookup(None).unwrap_err();
+        server.join().unwrap();
+
+        assert!(
+            matches!(&error, DetectError::Response { body, .. } if body == "<html></html>"),
+            "{error}"
+        );
+    }
+}
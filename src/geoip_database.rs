@@ -0,0 +1,162 @@
+//! Looks up the country of an IP address in a local database, without any network requests
+//!
+//! Supported formats are MaxMind DB files such as `GeoLite2-Country.mmdb` or
+//! `dbip-country-lite.mmdb`, and the DB-IP CSV files such as `dbip-country-lite.csv`
+
+use std::fs;
+use std::net::IpAddr;
+use std::net::Ipv4Addr;
+use std::net::Ipv6Addr;
+use std::net::SocketAddr;
+use std::net::UdpSocket;
+use std::path::PathBuf;
+
+use eyre::ContextCompat as _;
+use eyre::Result;
+use eyre::WrapErr as _;
+use eyre::bail;
+
+/// A local geo-IP database file
+pub struct Database {
+    /// Location of the `.mmdb` or `.csv` file
+    pub path: PathBuf,
+}
+
+impl Database {
+    /// Find the 2-letter country code (cca2) of `ip`
+    pub fn lookup(&self, ip: IpAddr) -> Result<String> {
+        let is_csv = self
+            .path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("csv"));
+
+        let cca2 = if is_csv {
+            self.lookup_csv(ip)?
+        } else {
+            self.lookup_mmdb(ip)?
+        };
+
+        cca2.wrap_err_with(|| format!("{ip} is not in {}", self.path.display()))
+    }
+
+    /// Read a database in the MaxMind DB format
+    fn lookup_mmdb(&self, ip: IpAddr) -> Result<Option<String>> {
+        let reader = maxminddb::Reader::open_readfile(&self.path)
+            .wrap_err_with(|| format!("failed to open {}", self.path.display()))?;
+
+        match reader.lookup::<maxminddb::geoip2::Country>(ip) {
+            Ok(record) => {
+                Ok(record
+                    .country
+                    .or(record.registered_country)
+                    .and_then(|country| country.iso_code)
+                    .map(str::to_string))
+            }
+            Err(maxminddb::MaxMindDBError::AddressNotFoundError(_)) => Ok(None),
+            Err(error) => {
+                Err(error).wrap_err_with(|| format!("failed to read {}", self.path.display()))
+            }
+        }
+    }
+
+    /// Read a database in the DB-IP CSV format, where each line is a range of
+    /// addresses followed by a country code: `1.0.0.0,1.0.0.255,AU`
+    fn lookup_csv(&self, ip: IpAddr) -> Result<Option<String>> {
+        let contents = fs::read_to_string(&self.path)
+            .wrap_err_with(|| format!("failed to read {}", self.path.display()))?;
+
+        for line in contents.lines() {
+            let mut columns = line
+                .split(',')
+                .map(|column| column.trim().trim_matches('"'));
+
+            let (Some(start), Some(end), Some(cca2)) =
+                (columns.next(), columns.next(), columns.next())
+            else {
+                continue;
+            };
+
+            let (Ok(start), Ok(end)) = (start.parse::<IpAddr>(), end.parse::<IpAddr>()) else {
+                continue;
+            };
+
+            let in_range = match (start, end, ip) {
+                (IpAddr::V4(start), IpAddr::V4(end), IpAddr::V4(ip)) => (start..=end).contains(&ip),
+                (IpAddr::V6(start), IpAddr::V6(end), IpAddr::V6(ip)) => (start..=end).contains(&ip),
+                _ => false,
+            };
+
+            if in_range {
+                return Ok(Some(cca2.to_string()));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// The address that this machine uses to reach the internet
+///
+/// No packets are sent: connecting a UDP socket only asks the operating system
+/// which local address it would route the traffic through. Behind a NAT this is a
+/// private address, which cannot be found in any geo-IP database.
+pub fn local_address() -> Result<IpAddr> {
+    let cloudflare_dns = [
+        SocketAddr::from((Ipv4Addr::new(1, 1, 1, 1), 53)),
+        SocketAddr::from((
+            Ipv6Addr::new(0x2606, 0x4700, 0x4700, 0, 0, 0, 0, 0x1111),
+            53,
+        )),
+    ];
+
+    let addresses = cloudflare_dns
+        .into_iter()
+        .filter_map(|remote| {
+            let local = match remote {
+                SocketAddr::V4(_) => SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)),
+                SocketAddr::V6(_) => SocketAddr::from((Ipv6Addr::UNSPECIFIED, 0)),
+            };
+
+            let socket = UdpSocket::bind(local).ok()?;
+            socket.connect(remote).ok()?;
+            Some(socket.local_addr().ok()?.ip())
+        })
+        .collect::<Vec<_>>();
+
+    match addresses.iter().find(|ip| is_public(**ip)) {
+        Some(ip) => Ok(*ip),
+        None => {
+            match addresses.first() {
+                Some(ip) => {
+                    bail!("the address of this machine ({ip}) is not public, so it has no country")
+                }
+                None => bail!("no network route found"),
+            }
+        }
+    }
+}
+
+/// Whether `ip` is reachable from the internet, and could be in a geo-IP database
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [first, second, ..] = ip.octets();
+            // 100.64.0.0/10 is used for carrier-grade NAT
+            let is_shared = first == 100 && (second & 0b1100_0000) == 64;
+
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || is_shared)
+        }
+        IpAddr::V6(ip) => {
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_unique_local()
+                || ip.is_unicast_link_local())
+        }
+    }
+}
@@ -0,0 +1,147 @@
+//! Places the flag of a country next to its information, or above it on narrow terminals
+
+use simply_colored::*;
+
+/// Space to the left of the flag
+const MARGIN: &str = "  ";
+
+/// Space between the flag and the information
+const GAP: &str = "  ";
+
+/// The information is never wrapped narrower than this, next to the flag.
+/// It is also the width when the width of the terminal is not known
+const MIN_INFO_WIDTH: usize = 40;
+
+/// Longer lines are hard to read, even when the terminal is wider
+const MAX_INFO_WIDTH: usize = 80;
+
+/// Lay out `flag` and `info`, which may contain ANSI escape sequences
+///
+/// Next to each other, the shorter of the two is vertically centered. When `width`
+/// is too narrow for both, the flag is above the information instead.
+pub fn render(flag: &str, info: &str, width: Option<usize>) -> String {
+    let flag_lines = flag.lines().collect::<Vec<_>>();
+    let flag_width = flag_lines
+        .iter()
+        .map(|line| textwrap::core::display_width(line))
+        .max()
+        .unwrap_or_default();
+
+    let info_width = match width {
+        Some(width) => {
+            width
+                .saturating_sub(MARGIN.len() + flag_width + GAP.len())
+                .min(MAX_INFO_WIDTH)
+        }
+        None => MIN_INFO_WIDTH,
+    };
+
+    if info_width < MIN_INFO_WIDTH {
+        return stacked(&flag_lines, info, width.unwrap_or_default());
+    }
+
+    let info_lines = wrap(info, info_width);
+
+    let height = flag_lines.len().max(info_lines.len());
+    let flag_top = (height - flag_lines.len()) / 2;
+    let info_top = (height - info_lines.len()) / 2;
+
+    let mut output = String::new();
+
+    for i in 0..height {
+        let flag_line = i
+            .checked_sub(flag_top)
+            .and_then(|i| flag_lines.get(i))
+            .copied()
+            .unwrap_or_default();
+        let info_line = i
+            .checked_sub(info_top)
+            .and_then(|i| info_lines.get(i))
+            .map(String::as_str)
+            .unwrap_or_default();
+
+        output.push_str(MARGIN);
+        output.push_str(flag_line);
+
+        if !info_line.is_empty() {
+            let padding = flag_width.saturating_sub(textwrap::core::display_width(flag_line));
+            output.push_str(&" ".repeat(padding));
+            output.push_str(GAP);
+            output.push_str(info_line);
+        }
+
+        output.push('\n');
+    }
+
+    output
+}
+
+/// The flag, then the information below it
+fn stacked(flag_lines: &[&str], info: &str, width: usize) -> String {
+    let mut output = String::new();
+
+    for line in flag_lines {
+        output.push_str(MARGIN);
+        output.push_str(line);
+        output.push('\n');
+    }
+
+    output.push('\n');
+
+    for line in wrap(info, width.saturating_sub(MARGIN.len()).max(1)) {
+        output.push_str(MARGIN);
+        output.push_str(&line);
+        output.push('\n');
+    }
+
+    output
+}
+
+/// Wrap `text` to `width` columns
+///
+/// A color that is active at the end of a line is reset there, and set again at the
+/// start of the next line. That way, the flag next to the text never takes its color.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    // Words longer than a line are left as they are, as splitting them could split an escape sequence.
+    // Splitting at hyphens would break apart values such as `UTC-03:00`
+    let options = textwrap::Options::new(width)
+        .break_words(false)
+        .word_splitter(textwrap::WordSplitter::NoHyphenation);
+
+    let mut active = String::new();
+
+    textwrap::wrap(text, options)
+        .into_iter()
+        .map(|line| {
+            let mut wrapped = format!("{active}{line}");
+
+            for sequence in sgr_sequences(&line) {
+                if matches!(sequence, "\x1b[0m" | "\x1b[m") {
+                    active.clear();
+                } else {
+                    active.push_str(sequence);
+                }
+            }
+
+            if !active.is_empty() {
+                wrapped.push_str(RESET);
+            }
+
+            wrapped
+        })
+        .collect()
+}
+
+/// Escape sequences that set colors and styles, such as `\x1b[1m`, in order
+fn sgr_sequences(text: &str) -> impl Iterator<Item = &str> {
+    text.match_indices('\x1b').filter_map(|(start, _)| {
+        let rest = &text[start..];
+        let end = rest.find('m')?;
+        let sequence = &rest[..=end];
+
+        sequence[2..end]
+            .chars()
+            .all(|c| c.is_ascii_digit() || c == ';')
+            .then_some(sequence)
+    })
+}
@@ -0,0 +1,113 @@
+//! Implements `--list-countries`, which shows every country that can be passed as an argument
+
+use std::io::Write as _;
+
+use clap::ValueEnum as _;
+use simply_colored::*;
+use strum::VariantArray as _;
+
+use crate::country_display;
+use crate::gen_countries::CountryKind;
+
+/// How to print the list of countries
+#[derive(clap::ValueEnum, Clone, Copy, Default, Debug)]
+pub enum ListFormat {
+    /// Aligned columns, meant for humans
+    #[default]
+    Plain,
+    /// Tab-separated values, one country per line
+    Tsv,
+    /// Array of JSON objects
+    Json,
+}
+
+/// A single row of the list
+#[derive(serde::Serialize)]
+struct Entry {
+    flag: Option<&'static str>,
+    name: &'static str,
+    cca2: &'static str,
+    cca3: &'static str,
+    /// Every name that the country can be passed as, e.g. `UnitedKingdom`, `GB`, `UK`
+    aliases: Vec<String>,
+}
+
+/// Print one row for each country, sorted by name
+pub fn print(format: ListFormat) -> eyre::Result<()> {
+    let mut entries = CountryKind::VARIANTS
+        .iter()
+        .map(|kind| (*kind, entry(*kind)))
+        .collect::<Vec<_>>();
+    entries.sort_by(|(_, a), (_, b)| a.name.cmp(b.name));
+
+    let mut stdout = anstream::stdout().lock();
+
+    match format {
+        ListFormat::Plain => {
+            let name_width = entries
+                .iter()
+                .map(|(_, entry)| textwrap::core::display_width(entry.name))
+                .max()
+                .unwrap_or_default();
+
+            for (kind, entry) in &entries {
+                let color = country_display::brightest_color(kind.data());
+                let padding = " ".repeat(name_width - textwrap::core::display_width(entry.name));
+
+                writeln!(
+                    stdout,
+                    "{flag} {color}{BOLD}{name}{RESET}{padding}  {cca2}  {cca3}  {DIM}{aliases}{RESET}",
+                    flag = entry.flag.unwrap_or("  "),
+                    name = entry.name,
+                    cca2 = entry.cca2,
+                    cca3 = entry.cca3,
+                    aliases = entry.aliases.join(", "),
+                )?;
+            }
+        }
+        ListFormat::Tsv => {
+            writeln!(stdout, "flag\tname\tcca2\tcca3\taliases")?;
+
+            for (_, entry) in &entries {
+                writeln!(
+                    stdout,
+                    "{}\t{}\t{}\t{}\t{}",
+                    entry.flag.unwrap_or_default(),
+                    entry.name,
+                    entry.cca2,
+                    entry.cca3,
+                    entry.aliases.join(",")
+                )?;
+            }
+        }
+        ListFormat::Json => {
+            let entries = entries.iter().map(|(_, entry)| entry).collect::<Vec<_>>();
+            writeln!(stdout, "{}", colored_json::to_colored_json_auto(&entries)?)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Collect everything we want to show about a country
+fn entry(kind: CountryKind) -> Entry {
+    let country = kind.data();
+
+    let mut aliases: Vec<String> = Vec::new();
+    if let Some(value) = kind.to_possible_value() {
+        for alias in value.get_name_and_aliases() {
+            // The generated enum can contain the same alias more than once
+            if !aliases.iter().any(|existing| existing == alias) {
+                aliases.push(alias.to_string());
+            }
+        }
+    }
+
+    Entry {
+        flag: country.flag.as_deref(),
+        name: &country.name.common,
+        cca2: &country.cca2,
+        cca3: &country.cca3,
+        aliases,
+    }
+}
@@ -1,81 +1,303 @@
-//! Things left to do
-//!
-//! - Vertically align the country flag in the middle
-//! - Fancy display for the "Neighbours", so it shows the full country name
-//! - Fix numeric value of the "Area" field
-//! - Round population to the nearest million, e.g "13.4 million" or "63.1 thousand" instead of exact count
-
 use std::io::Write;
 
-use anstream::println;
+use anstream::eprintln;
 use clap::Parser;
-use eyre::ContextCompat;
 use eyre::Result;
-use serde::Deserialize;
+use eyre::WrapErr as _;
+use eyre::bail;
+use eyre::eyre;
 
-use crate::countries::COUNTRIES_DATA;
 use crate::countries::Country;
+use crate::gen_countries::CountryKind;
 
+mod cache;
 mod cli;
+mod compare;
+mod config;
 mod countries;
 mod country_display;
+mod detect;
+mod dialing_code;
+mod export;
 mod extra_country_data;
+mod fields;
+mod filter;
 mod gen_countries;
+mod geoip;
+mod geoip_database;
+mod layout;
+mod list_countries;
+mod names;
+mod numbers;
+mod query;
+mod report;
+mod sort;
+mod table;
+mod template;
+mod timezones;
+mod units;
 
 fn main() -> Result<()> {
+    match run() {
+        // Output piped into a command such as `head` can be closed before everything
+        // is written, which is how scripts normally read only part of it
+        Err(error) if is_broken_pipe(&error) => Ok(()),
+        result => result,
+    }
+}
+
+/// Whether the error comes from writing to a pipe whose reader has exited
+fn is_broken_pipe(error: &eyre::Report) -> bool {
+    error.chain().any(|error| {
+        error
+            .downcast_ref::<std::io::Error>()
+            .is_some_and(|error| error.kind() == std::io::ErrorKind::BrokenPipe)
+    })
+}
+
+fn run() -> Result<()> {
     let cli = <cli::Cli as Parser>::parse();
+    let config = config::Config::load(cli.config.as_deref())?;
 
-    match cli.all {
-        false => {
-            let country = if let Some(country) = cli.country {
-                country.data()
-            } else {
-                // No specific country provided, so we'll detect it
+    if let Some(command) = &cli.command {
+        return match command {
+            cli::Command::List { format } => list_countries::print(*format),
+            cli::Command::Compare { countries, json } => {
+                let countries = dedup_countries(countries);
 
-                #[derive(Deserialize)]
-                struct Response {
-                    /// 2-letter country code (cca2)
-                    country: String,
+                // The same country spelled in two ways, e.g. `compare de germany`
+                if countries.len() < 2 {
+                    bail!("compare needs at least 2 different countries");
                 }
 
-                // get current country
-                let cca2 = ureq::get("https://api.country.is")
-                    .header("User-Agent", "countryfetch")
-                    .call()?
-                    .body_mut()
-                    .read_json::<Response>()?
-                    .country;
-
-                COUNTRIES_DATA
-                    .0
-                    .iter()
-                    .find(|country| country.cca2 == cca2)
-                    .wrap_err_with(|| format!("no country with cca2 {cca2} found"))?
-            };
-
-            if cli.json {
-                println!("{}", colored_json::to_colored_json_auto(country)?)
-            } else {
-                println!("\n{}", country)
+                if *json {
+                    compare::print_json(&countries)
+                } else {
+                    compare::print(&countries, &country_display::Options {
+                        numbers: numbers::NumberFormat::new(cli.numbers),
+                        units: cli.units,
+                        at: cli.at,
+                        lang: cli.lang.clone(),
+                        ..Default::default()
+                    })
+                }
             }
+        };
+    }
+
+    // Filtering, sorting, limiting and tables without any countries apply to all of them
+    let is_list = cli.filter.is_some() || cli.sort_by.is_some() || cli.limit.is_some() || cli.table;
+    let all = cli.all
+        || (is_list && cli.country.is_empty() && cli.ip.is_none() && cli.dialing_code.is_none());
+
+    let mut countries: Vec<&Country> = if all {
+        gen_countries::all_countries().to_vec()
+    } else if let Some(ip) = cli.ip {
+        let (country, source) = detector(&cli, &config).locate(ip)?;
+
+        if cli.verbose {
+            eprintln!("ip: found {} from {source}", country.name.common);
         }
-        true => {
-            let countries: &[&Country] = &gen_countries::all_countries();
 
-            if cli.json {
-                println!("{}", colored_json::to_colored_json_auto(&countries)?);
-            } else {
-                let mut stdout = anstream::stdout().lock();
+        vec![country]
+    } else if let Some(code) = &cli.dialing_code {
+        let countries = dialing_code::find(code);
+
+        if countries.is_empty() {
+            bail!("no country has the dialing code {code}");
+        }
+
+        countries
+    } else if cli.country.is_empty() {
+        // No specific country provided, so we'll detect it
+        let detection = detector(&cli, &config).detect()?;
+
+        if cli.verbose {
+            eprintln!("{}", detection.report());
+        }
+
+        vec![detection.country]
+    } else {
+        dedup_countries(&cli.country)
+    };
+
+    if let Some(filter) = &cli.filter {
+        countries.retain(|country| filter.matches(country));
+    }
+
+    if let Some(key) = cli.sort_by {
+        sort::sort(&mut countries, key, cli.desc);
+    }
+
+    if let Some(limit) = cli.limit {
+        countries.truncate(limit);
+    }
+
+    if let Some(path) = &cli.get {
+        let mut stdout = anstream::stdout().lock();
+        let mut missing = Vec::new();
+
+        for country in &countries {
+            match query::get_lines(country, path) {
+                Some(lines) => {
+                    for line in lines {
+                        writeln!(stdout, "{line}")?;
+                    }
+                }
+                None => missing.push(country.name.common.as_str()),
+            }
+        }
+
+        if !missing.is_empty() {
+            bail!("`{path}` not found for {}", missing.join(", "));
+        }
 
+        return Ok(());
+    }
+
+    let options = country_display::Options {
+        fields: fields::Selection {
+            only: cli.fields.clone(),
+            hidden: cli.hide.clone(),
+        },
+        numbers: numbers::NumberFormat::new(cli.numbers),
+        units: cli.units,
+        expand_borders: cli.expand_borders,
+        width: terminal_size::terminal_size()
+            .map(|(terminal_size::Width(width), _)| usize::from(width)),
+        at: cli.at,
+        lang: cli.lang.clone(),
+    };
+
+    if cli.table {
+        return table::print(&countries, &options);
+    }
+
+    let format = if cli.json {
+        export::Format::Json
+    } else {
+        cli.format
+    };
+
+    // A single country is printed on its own. When more countries could
+    // have been printed, they are in a list even if there is just one
+    let is_list = all || is_list || cli.country.len() > 1 || cli.dialing_code.is_some();
+
+    let mut stdout = anstream::stdout().lock();
+
+    match format {
+        export::Format::Pretty => {
+            // The template from the config replaces the default output, but not other formats
+            if let Some(path) = cli.template.as_ref().or(config.template.as_ref()) {
+                let template = load_template(path)?;
+
+                for country in countries {
+                    stdout.write_all(template.render(country)?.as_bytes())?;
+                }
+            } else {
                 stdout.write_all(b"\n")?;
 
                 for country in countries {
-                    stdout.write_all(country.to_string().as_bytes())?;
+                    stdout.write_all(country.display(&options).to_string().as_bytes())?;
                     stdout.write_all(b"\n")?;
                 }
             }
         }
+        export::Format::Json => {
+            export::write_json(&mut stdout, &countries, &options, is_list)?;
+        }
+        export::Format::Ndjson => {
+            export::write_ndjson(&mut stdout, &countries, &options)?;
+        }
+        export::Format::Yaml => {
+            export::write_yaml(&mut stdout, &countries, &options, is_list)?;
+        }
+        export::Format::Toml => {
+            export::write_toml(&mut stdout, &countries, &options, is_list)?;
+        }
+        export::Format::Markdown => {
+            report::write_markdown(&mut stdout, &countries, &options)?;
+        }
+        export::Format::Html => {
+            report::write_html(&mut stdout, &countries, &options)?;
+        }
+        export::Format::Csv => {
+            export::write_csv(&mut stdout, &countries, &options.fields)?;
+        }
+        export::Format::Tsv => {
+            export::write_tsv(&mut stdout, &countries, &options.fields)?;
+        }
     }
 
     Ok(())
 }
+
+/// The countries of `kinds`, in order
+///
+/// The same country can be spelled in multiple ways, e.g. `gb UnitedKingdom`.
+/// It is only included once, at the position where it was first mentioned
+fn dedup_countries(kinds: &[CountryKind]) -> Vec<&'static Country> {
+    let mut unique = Vec::with_capacity(kinds.len());
+
+    for kind in kinds.iter().copied() {
+        if !unique.contains(&kind) {
+            unique.push(kind);
+        }
+    }
+
+    unique.into_iter().map(CountryKind::data).collect()
+}
+
+/// Read and parse the template file at `path`
+fn load_template(path: &std::path::Path) -> Result<template::Template> {
+    std::fs::read_to_string(path)
+        .wrap_err_with(|| format!("failed to read template {}", path.display()))?
+        .parse()
+        .map_err(|error| eyre!("invalid template {}: {error}", path.display()))
+}
+
+/// Combine the command-line arguments with the config to decide how to detect the country
+fn detector(cli: &cli::Cli, config: &config::Config) -> detect::Detector {
+    let database = cli
+        .geoip_db
+        .clone()
+        .or_else(|| config.geoip_db.clone())
+        .map(|path| geoip_database::Database { path });
+
+    detect::Detector {
+        strategies: if cli.detect.is_empty() {
+            detect::default_strategies(database.is_some())
+        } else {
+            cli.detect.clone()
+        },
+        provider: geoip::Provider {
+            url: cli
+                .geoip_url
+                .clone()
+                .or_else(|| config.geoip_url.clone())
+                .unwrap_or_else(|| geoip::DEFAULT_URL.to_string()),
+            schema: cli.geoip_schema.or(config.geoip_schema).unwrap_or_default(),
+            network: geoip::Network {
+                connect_timeout: cli
+                    .connect_timeout
+                    .or(config.connect_timeout)
+                    .unwrap_or(geoip::Network::DEFAULT_CONNECT_TIMEOUT),
+                read_timeout: cli
+                    .read_timeout
+                    .or(config.read_timeout)
+                    .unwrap_or(geoip::Network::DEFAULT_READ_TIMEOUT),
+                retries: cli
+                    .retries
+                    .or(config.retries)
+                    .unwrap_or(geoip::Network::DEFAULT_RETRIES),
+            },
+        },
+        database,
+        cache: cache::Cache::new(
+            cli.cache_ttl
+                .or(config.cache_ttl)
+                .unwrap_or(cache::DEFAULT_TTL),
+        ),
+        refresh: cli.refresh,
+    }
+}
@@ -0,0 +1,91 @@
+//! Names of countries in other languages, for the `names` field and `--lang`
+
+use std::collections::BTreeSet;
+
+use crate::countries::COUNTRIES_DATA;
+use crate::countries::Country;
+use crate::countries::Translation;
+use crate::country_display::Options;
+
+/// Name of the country in the language of `--lang`, e.g. `Allemagne` for Germany in `fra`
+///
+/// When there is no translation into that language, the native name in it is used,
+/// and otherwise the English name.
+pub fn name<'a>(country: &'a Country, options: &Options) -> &'a str {
+    options
+        .lang
+        .as_deref()
+        .and_then(|lang| {
+            country.translations.get(lang).or_else(|| {
+                country
+                    .name
+                    .native_name
+                    .as_ref()
+                    .and_then(|names| names.get(lang))
+            })
+        })
+        .map_or(&country.name.common, |translation| &translation.common)
+}
+
+/// Names of the country in each of its languages, e.g. `Deutschland (Bundesrepublik Deutschland)`.
+/// With multiple languages, each name starts with the language. Also returns how many names there are
+pub fn native(country: &Country) -> Option<(String, usize)> {
+    let mut names = country
+        .name
+        .native_name
+        .iter()
+        .flatten()
+        .collect::<Vec<_>>();
+    names.sort_by_key(|(language, _)| *language);
+
+    let describe = |name: &Translation| {
+        if name.official == name.common {
+            name.common.clone()
+        } else {
+            format!("{} ({})", name.common, name.official)
+        }
+    };
+
+    let value = match names.as_slice() {
+        [] => return None,
+        [(_, name)] => describe(name),
+        names => {
+            names
+                .iter()
+                .map(|(code, name)| {
+                    let language = country
+                        .languages
+                        .as_ref()
+                        .and_then(|languages| languages.get(*code))
+                        .unwrap_or(code);
+
+                    format!("{language}: {}", describe(name))
+                })
+                .collect::<Vec<_>>()
+                .join("; ")
+        }
+    };
+
+    Some((value, names.len()))
+}
+
+/// Parse a language for `--lang`, which must be one that countries are translated into
+pub fn parse_language(language: &str) -> Result<String, String> {
+    let language = language.to_lowercase();
+
+    let languages = COUNTRIES_DATA
+        .0
+        .iter()
+        .flat_map(|country| country.translations.keys())
+        .map(String::as_str)
+        .collect::<BTreeSet<_>>();
+
+    if languages.contains(language.as_str()) {
+        Ok(language)
+    } else {
+        Err(format!(
+            "no translations into `{language}`, choose any of: {}",
+            languages.into_iter().collect::<Vec<_>>().join(", ")
+        ))
+    }
+}
@@ -0,0 +1,217 @@
+//! Writing numbers such as population and area, with `--numbers`
+
+/// How numbers are written
+#[derive(clap::ValueEnum, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum Notation {
+    /// Every digit, e.g. `13,412,000`
+    #[default]
+    Exact,
+    /// Rounded to thousands, millions or billions, e.g. `13.4 million`
+    Compact,
+}
+
+/// Characters that group digits and separate decimals, which differ between locales
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Separators {
+    thousands: char,
+    decimal: char,
+}
+
+impl Default for Separators {
+    fn default() -> Self {
+        Self {
+            thousands: ',',
+            decimal: '.',
+        }
+    }
+}
+
+impl Separators {
+    /// Separators of the locale in `LC_ALL`, `LC_NUMERIC` or `LANG`
+    fn from_env() -> Self {
+        let locale = ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .into_iter()
+            .filter_map(|variable| std::env::var(variable).ok())
+            .find(|locale| !locale.is_empty())
+            .unwrap_or_default();
+
+        Self::from_locale(&locale)
+    }
+
+    /// Separators of a locale such as `de_DE.UTF-8`
+    fn from_locale(locale: &str) -> Self {
+        // `de_CH.UTF-8@euro` is `de` and `CH`
+        let locale = locale.split(['.', '@']).next().unwrap_or_default();
+        let (language, region) = locale.split_once('_').unwrap_or((locale, ""));
+
+        // A non-breaking space, so that numbers are never wrapped onto two lines
+        let space = '\u{a0}';
+
+        let (thousands, decimal) = match (language, region) {
+            ("de" | "it" | "fr", "CH") | ("rm", _) => ('\'', '.'),
+            ("pt", "PT") => (space, ','),
+            (
+                "de" | "es" | "it" | "nl" | "pt" | "da" | "id" | "tr" | "el" | "ro" | "hr" | "sl"
+                | "sr" | "vi" | "is",
+                _,
+            ) => ('.', ','),
+            (
+                "fr" | "ru" | "pl" | "cs" | "sk" | "uk" | "fi" | "sv" | "nb" | "nn" | "no" | "hu"
+                | "bg" | "lt" | "lv" | "et" | "be" | "kk",
+                _,
+            ) => (space, ','),
+            _ => return Self::default(),
+        };
+
+        Self { thousands, decimal }
+    }
+}
+
+/// Writes numbers in the chosen notation, with the separators of the user's locale
+#[derive(Clone, Copy, Default, Debug)]
+pub struct NumberFormat {
+    notation: Notation,
+    separators: Separators,
+}
+
+impl NumberFormat {
+    /// Numbers in `notation`, with separators of the user's locale
+    pub fn new(notation: Notation) -> Self {
+        Self {
+            notation,
+            separators: Separators::from_env(),
+        }
+    }
+
+    /// The same notation, always with `,` between thousands and `.` before decimals
+    pub fn without_locale(self) -> Self {
+        Self {
+            separators: Separators::default(),
+            ..self
+        }
+    }
+
+    /// Write `number` with at most `decimals` decimal places, e.g. `83,491,249`
+    /// or `83.5 million`
+    pub fn format(&self, number: f64, decimals: usize) -> String {
+        const UNITS: [(f64, &str); 4] = [
+            (1e12, "trillion"),
+            (1e9, "billion"),
+            (1e6, "million"),
+            (1e3, "thousand"),
+        ];
+
+        if self.notation == Notation::Compact
+            && let Some((size, unit)) = UNITS.iter().find(|(size, _)| {
+                // Rounded the way it would be written with the next smaller unit,
+                // so that 999,960 is `1 million` rather than `1,000 thousand`
+                let smaller = size / 1e3;
+                let decimals = if smaller == 1.0 { decimals } else { 1 };
+                round(number.abs() / smaller, decimals) >= 1e3
+            })
+        {
+            // A non-breaking space keeps the unit on the same line as the number
+            return format!("{}\u{a0}{unit}", self.exact(number / size, 1));
+        }
+
+        self.exact(number, decimals)
+    }
+
+    /// Every digit of `number`, grouped by thousands. Trailing zero decimals are left out
+    fn exact(&self, number: f64, decimals: usize) -> String {
+        let formatted = format!("{:.decimals$}", number.abs());
+        let (integer, fraction) = formatted
+            .split_once('.')
+            .unwrap_or((formatted.as_str(), ""));
+        let fraction = fraction.trim_end_matches('0');
+
+        let mut output = String::new();
+        if number < 0.0 && formatted.chars().any(|c| c.is_ascii_digit() && c != '0') {
+            output.push('-');
+        }
+
+        for (i, digit) in integer.chars().enumerate() {
+            if i > 0 && (integer.len() - i) % 3 == 0 {
+                output.push(self.separators.thousands);
+            }
+            output.push(digit);
+        }
+
+        if !fraction.is_empty() {
+            output.push(self.separators.decimal);
+            output.push_str(fraction);
+        }
+
+        output
+    }
+}
+
+/// `number` rounded to `decimals` decimal places, the same way as when it is written
+fn round(number: f64, decimals: usize) -> f64 {
+    format!("{number:.decimals$}").parse().unwrap_or(number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(notation: Notation, locale: &str, number: f64, decimals: usize) -> String {
+        NumberFormat {
+            notation,
+            separators: Separators::from_locale(locale),
+        }
+        .format(number, decimals)
+        .replace('\u{a0}', " ")
+    }
+
+    #[test]
+    fn separators() {
+        let exact = |locale| format(Notation::Exact, locale, 1_234_567.89, 2);
+
+        assert_eq!(exact(""), "1,234,567.89");
+        assert_eq!(exact("C"), "1,234,567.89");
+        assert_eq!(exact("en_US.UTF-8"), "1,234,567.89");
+        assert_eq!(exact("de_DE.UTF-8"), "1.234.567,89");
+        assert_eq!(exact("de_CH.UTF-8"), "1'234'567.89");
+        assert_eq!(exact("rm_CH"), "1'234'567.89");
+        assert_eq!(exact("fr_FR.UTF-8@euro"), "1 234 567,89");
+        assert_eq!(exact("fr_CH"), "1'234'567.89");
+        assert_eq!(exact("pt_PT"), "1 234 567,89");
+        assert_eq!(exact("pt_BR"), "1.234.567,89");
+        assert_eq!(exact("ru_RU"), "1 234 567,89");
+    }
+
+    #[test]
+    fn exact() {
+        let exact = |number, decimals| format(Notation::Exact, "", number, decimals);
+
+        assert_eq!(exact(0.0, 0), "0");
+        assert_eq!(exact(999.0, 0), "999");
+        assert_eq!(exact(1000.0, 0), "1,000");
+        assert_eq!(exact(0.49, 2), "0.49");
+        assert_eq!(exact(2.5, 2), "2.5");
+        assert_eq!(exact(-1234.5, 1), "-1,234.5");
+        assert_eq!(exact(-0.01, 1), "0");
+    }
+
+    #[test]
+    fn compact() {
+        let compact = |number| format(Notation::Compact, "", number, 0);
+
+        assert_eq!(compact(999.0), "999");
+        assert_eq!(compact(999.4), "999");
+        assert_eq!(compact(999.6), "1 thousand");
+        assert_eq!(compact(1000.0), "1 thousand");
+        assert_eq!(compact(13_412_000.0), "13.4 million");
+        assert_eq!(compact(999_940.0), "999.9 thousand");
+        assert_eq!(compact(999_960.0), "1 million");
+        assert_eq!(compact(999_960_000.0), "1 billion");
+        assert_eq!(compact(1_417_492_000.0), "1.4 billion");
+        assert_eq!(compact(2e15), "2,000 trillion");
+        assert_eq!(compact(-2_500_000.0), "-2.5 million");
+        assert_eq!(
+            format(Notation::Compact, "de_DE", 83_491_249.0, 0),
+            "83,5 million"
+        );
+    }
+}
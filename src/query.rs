@@ -0,0 +1,78 @@
+//! Looks up a single value of a country, with `--get`
+
+use serde_json::Value;
+
+use crate::countries::Country;
+
+/// Follow a dotted path such as `currencies.JPY.symbol` or `capital.0` into `value`
+///
+/// Keys are matched ignoring case and underscores, so `capital_info` finds `capitalInfo`.
+/// Missing values and `null` are both `None`.
+pub fn get<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    let value = path
+        .split('.')
+        .filter(|segment| !segment.is_empty())
+        .try_fold(value, |value, segment| {
+            match value {
+                Value::Object(map) => {
+                    map.get(segment).or_else(|| {
+                        map.iter()
+                            .find(|(key, _)| normalize(key) == normalize(segment))
+                            .map(|(_, value)| value)
+                    })
+                }
+                Value::Array(values) => values.get(segment.parse::<usize>().ok()?),
+                _ => None,
+            }
+        })?;
+
+    (!value.is_null()).then_some(value)
+}
+
+/// Key without case and underscores: `capital_info` and `capitalInfo` are both `capitalinfo`
+fn normalize(key: &str) -> String {
+    key.chars()
+        .filter(|c| *c != '_')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Value at `path` of the country, as raw lines of text, or `None` if the country does not have it
+///
+/// Strings are printed without quotes, and each item of an array is on its own line.
+/// Objects are printed as a key and a value separated by a tab, sorted by key.
+pub fn get_lines(country: &Country, path: &str) -> Option<Vec<String>> {
+    // `idd` is split into a root and suffixes, but what you want is the whole dialing code
+    if matches!(normalize(path).as_str(), "idd" | "dialingcodes") {
+        let codes = crate::dialing_code::codes(country);
+        return (!codes.is_empty()).then_some(codes);
+    }
+
+    let country = serde_json::to_value(country).ok()?;
+
+    let lines: Vec<String> = match get(&country, path)? {
+        Value::Array(values) => values.iter().map(raw).collect(),
+        Value::Object(map) => {
+            // Sorted by key, as the order of a `HashMap` changes on every run
+            let mut entries = map.iter().collect::<Vec<_>>();
+            entries.sort_by_key(|(key, _)| *key);
+
+            entries
+                .into_iter()
+                .map(|(key, value)| format!("{key}\t{}", raw(value)))
+                .collect()
+        }
+        value => vec![raw(value)],
+    };
+
+    // An empty list of capitals is the same as having no capital
+    (!lines.is_empty()).then_some(lines)
+}
+
+/// Value without quotes around strings. Arrays and objects stay as compact JSON
+fn raw(value: &Value) -> String {
+    match value {
+        Value::String(string) => string.clone(),
+        value => value.to_string(),
+    }
+}
@@ -0,0 +1,213 @@
+//! Fact sheets of countries in Markdown and HTML, with `--format markdown` and `--format html`
+
+use std::io::Write;
+
+use crate::countries::Country;
+use crate::country_display;
+use crate::country_display::Options;
+use crate::fields::Field;
+
+/// Write a Markdown fact sheet for each country
+pub fn write_markdown(
+    writer: &mut impl Write,
+    countries: &[&Country],
+    options: &Options,
+) -> eyre::Result<()> {
+    for (i, country) in countries.iter().enumerate() {
+        if i > 0 {
+            writeln!(writer)?;
+        }
+
+        let name = crate::names::name(country, options);
+
+        match &country.flag {
+            Some(flag) => writeln!(writer, "# {flag} {name}")?,
+            None => writeln!(writer, "# {name}")?,
+        }
+        writeln!(writer)?;
+
+        // The PNG is shown, because not every wiki renders SVG. It links to the SVG
+        writeln!(
+            writer,
+            "[![{}]({})]({})",
+            markdown_escape(&flag_alt(country)),
+            country.flags.png,
+            country.flags.svg
+        )?;
+        writeln!(writer)?;
+        writeln!(writer, "*{}*", markdown_escape(&country.name.official))?;
+        writeln!(writer)?;
+
+        writeln!(writer, "| | |")?;
+        writeln!(writer, "| --- | --- |")?;
+        for (label, value) in facts(country, options) {
+            writeln!(
+                writer,
+                "| **{}** | {} |",
+                markdown_escape(&label),
+                markdown_escape(&value)
+            )?;
+        }
+        writeln!(writer)?;
+
+        writeln!(
+            writer,
+            "**Maps:** [Google Maps]({}) · [OpenStreetMap]({})",
+            country.maps.google_maps, country.maps.open_street_maps
+        )?;
+
+        if let Some(coat_of_arms) = coat_of_arms(country) {
+            writeln!(writer)?;
+            writeln!(writer, "## Coat of arms")?;
+            writeln!(writer)?;
+            writeln!(
+                writer,
+                "![Coat of arms of {}]({coat_of_arms})",
+                markdown_escape(name)
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Write an HTML fact sheet for each country, as an `<article>` that can be embedded into a page
+pub fn write_html(
+    writer: &mut impl Write,
+    countries: &[&Country],
+    options: &Options,
+) -> eyre::Result<()> {
+    for country in countries {
+        let name = html_escape(crate::names::name(country, options));
+
+        writeln!(writer, r#"<article class="country" id="{}">"#, country.cca3)?;
+
+        match &country.flag {
+            Some(flag) => writeln!(writer, "  <h1>{flag} {name}</h1>")?,
+            None => writeln!(writer, "  <h1>{name}</h1>")?,
+        }
+
+        writeln!(writer, "  <picture>")?;
+        writeln!(
+            writer,
+            r#"    <source srcset="{}" type="image/svg+xml">"#,
+            html_escape(&country.flags.svg)
+        )?;
+        writeln!(
+            writer,
+            r#"    <img src="{}" alt="{}">"#,
+            html_escape(&country.flags.png),
+            html_escape(&flag_alt(country))
+        )?;
+        writeln!(writer, "  </picture>")?;
+        writeln!(
+            writer,
+            "  <p><em>{}</em></p>",
+            html_escape(&country.name.official)
+        )?;
+
+        writeln!(writer, "  <table>")?;
+        for (label, value) in facts(country, options) {
+            writeln!(
+                writer,
+                "    <tr><th>{}</th><td>{}</td></tr>",
+                html_escape(&label),
+                html_escape(&value)
+            )?;
+        }
+        writeln!(writer, "  </table>")?;
+
+        writeln!(
+            writer,
+            r#"  <p>Maps: <a href="{}">Google Maps</a> · <a href="{}">OpenStreetMap</a></p>"#,
+            html_escape(&country.maps.google_maps),
+            html_escape(&country.maps.open_street_maps)
+        )?;
+
+        if let Some(coat_of_arms) = coat_of_arms(country) {
+            writeln!(writer, "  <h2>Coat of arms</h2>")?;
+            writeln!(
+                writer,
+                r#"  <img src="{}" alt="Coat of arms of {name}">"#,
+                html_escape(coat_of_arms)
+            )?;
+        }
+
+        writeln!(writer, "</article>")?;
+    }
+
+    Ok(())
+}
+
+/// Label and value of every field that is shown, the same as in the default output
+///
+/// Fact sheets are embedded into wikis, so they must be the same every time they are
+/// generated. That is why timezones are only the offsets, without the current time there,
+/// and numbers are written the same way whatever the locale of the machine is
+fn facts(country: &Country, options: &Options) -> Vec<(String, String)> {
+    let options = &Options {
+        fields: options.fields.clone(),
+        numbers: options.numbers.without_locale(),
+        lang: options.lang.clone(),
+        ..*options
+    };
+
+    options
+        .fields
+        .fields()
+        .into_iter()
+        .filter_map(|field| {
+            match field {
+                Field::Timezones if !country.timezones.is_empty() => {
+                    let label = if country.timezones.len() == 1 {
+                        "Timezone"
+                    } else {
+                        "Timezones"
+                    };
+
+                    Some((label.to_string(), country.timezones.join(", ")))
+                }
+                field => country_display::field_value(country, field, options),
+            }
+        })
+        .collect()
+}
+
+/// Description of the flag for people who cannot see it
+fn flag_alt(country: &Country) -> String {
+    country
+        .flags
+        .alt
+        .clone()
+        .unwrap_or_else(|| format!("Flag of {}", country.name.common))
+}
+
+/// Image of the coat of arms, preferring SVG. Not every country has one
+fn coat_of_arms(country: &Country) -> Option<&str> {
+    country
+        .coat_of_arms
+        .svg
+        .as_deref()
+        .or(country.coat_of_arms.png.as_deref())
+}
+
+/// Escape characters that would end a table cell, a link or an image description
+fn markdown_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        if matches!(c, '\\' | '|' | '[' | ']' | '*' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
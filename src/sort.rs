@@ -0,0 +1,68 @@
+//! Sorting of multiple countries, with `--sort-by`
+
+use std::cmp::Ordering;
+
+use crate::countries::Country;
+
+/// What to sort countries by
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum SortKey {
+    /// Number of people
+    Population,
+    /// Area in km²
+    Area,
+    /// People per km²
+    Density,
+    /// Common name
+    Name,
+    /// Most recent Gini coefficient, which measures income inequality
+    Gini,
+}
+
+/// Sort `countries` by `key`, in ascending order unless `descending`
+///
+/// Countries without a value for `key`, such as ones without a Gini coefficient,
+/// are always placed last.
+pub fn sort(countries: &mut [&Country], key: SortKey, descending: bool) {
+    countries.sort_by(|a, b| {
+        let ordering = match key {
+            SortKey::Name => Some(a.name.common.cmp(&b.name.common)),
+            SortKey::Population => Some(a.population.cmp(&b.population)),
+            SortKey::Area => a.area.partial_cmp(&b.area),
+            SortKey::Density => {
+                match (density(a), density(b)) {
+                    (Some(a), Some(b)) => a.partial_cmp(&b),
+                    (a, b) => return a.is_none().cmp(&b.is_none()),
+                }
+            }
+            SortKey::Gini => {
+                match (latest_gini(a), latest_gini(b)) {
+                    (Some(a), Some(b)) => a.partial_cmp(&b),
+                    (a, b) => return a.is_none().cmp(&b.is_none()),
+                }
+            }
+        }
+        .unwrap_or(Ordering::Equal);
+
+        if descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+}
+
+/// People per km², if the country has any area
+pub fn density(country: &Country) -> Option<f64> {
+    (country.area > 0.0).then(|| country.population as f64 / country.area)
+}
+
+/// Gini coefficient of the most recent year that it was measured in
+pub fn latest_gini(country: &Country) -> Option<f64> {
+    country
+        .gini
+        .as_ref()?
+        .iter()
+        .max_by(|(year_a, _), (year_b, _)| year_a.cmp(year_b))
+        .map(|(_, gini)| *gini)
+}
@@ -0,0 +1,224 @@
+//! Prints countries as a table with `--table`, one row per country
+
+use std::io::Write as _;
+
+use simply_colored::*;
+
+use crate::countries::Country;
+use crate::country_display;
+use crate::country_display::Options;
+use crate::fields::Field;
+
+/// Space between two columns
+const GAP: &str = "  ";
+
+/// Columns are never truncated to less than this
+const MIN_WIDTH: usize = 4;
+
+/// Print one row for each country. Columns are chosen by `--fields` and `--hide`,
+/// and shrink to fit the width of the terminal
+pub fn print(countries: &[&Country], options: &Options) -> eyre::Result<()> {
+    let fields = options.fields.fields_or(Field::TABLE);
+
+    let header = std::iter::once("Country".to_string())
+        .chain(fields.iter().map(|field| header(*field, options)))
+        .collect::<Vec<_>>();
+
+    let rows = countries
+        .iter()
+        .map(|country| {
+            let name = crate::names::name(country, options);
+            let name = match &country.flag {
+                Some(flag) => format!("{flag} {name}"),
+                None => name.to_string(),
+            };
+
+            std::iter::once(name)
+                .chain(fields.iter().map(|field| cell(country, *field, options)))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let mut widths = header
+        .iter()
+        .map(|header| textwrap::core::display_width(header))
+        .collect::<Vec<_>>();
+
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(textwrap::core::display_width(cell));
+        }
+    }
+
+    if let Some((terminal_size::Width(terminal_width), _)) = terminal_size::terminal_size() {
+        fit(&mut widths, usize::from(terminal_width));
+    }
+
+    let mut stdout = anstream::stdout().lock();
+
+    let header = header
+        .iter()
+        .zip(&widths)
+        .enumerate()
+        .map(|(i, (header, width))| align(header, *width, is_numeric(&fields, i)))
+        .collect::<Vec<_>>()
+        .join(GAP);
+    writeln!(stdout, "{BOLD}{}{RESET}", header.trim_end())?;
+
+    for (country, row) in countries.iter().zip(&rows) {
+        let line = row
+            .iter()
+            .zip(&widths)
+            .enumerate()
+            .map(|(i, (cell, width))| {
+                let cell = align(cell, *width, is_numeric(&fields, i));
+
+                // The name of each country is in the color of its flag
+                if i == 0 {
+                    format!("{}{cell}{RESET}", country_display::brightest_color(country))
+                } else {
+                    cell
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(GAP);
+
+        writeln!(stdout, "{}", line.trim_end())?;
+    }
+
+    Ok(())
+}
+
+/// Title of the column for `field`
+fn header(field: Field, options: &Options) -> String {
+    match field {
+        Field::Names => "Native names",
+        Field::Area => return format!("Area ({})", options.units.area_unit()),
+        Field::Density => return format!("Density (per {})", options.units.area_unit()),
+        Field::SquareSide => return format!("Square side ({})", options.units.length_unit()),
+        Field::Continents => "Continents",
+        Field::Region => "Region",
+        Field::Population => "Population",
+        Field::Neighbours => "Neighbours",
+        Field::Capital => "Capital",
+        Field::Timezones => "Timezones",
+        Field::IsoCodes => "Code",
+        Field::DrivingSide => "Driving side",
+        Field::DialingCode => "Dialing code",
+        Field::Languages => "Languages",
+        Field::Currencies => "Currencies",
+        Field::Tld => "TLD",
+        Field::Palette => "Palette",
+    }
+    .to_string()
+}
+
+/// Value of `field` for a country, on a single line. Missing values are `-`
+fn cell(country: &Country, field: Field, options: &Options) -> String {
+    let value = match field {
+        Field::Area => {
+            let area = options.units.area_value(country.area);
+            options.numbers.format(area, crate::units::decimals(area))
+        }
+        Field::Population => options.numbers.format(country.population as f64, 0),
+        Field::Density => {
+            crate::sort::density(country)
+                .map(|density| {
+                    options
+                        .numbers
+                        .format(options.units.density_value(density), 1)
+                })
+                .unwrap_or_default()
+        }
+        Field::SquareSide => {
+            let side = options.units.length_value(country.area.sqrt());
+            options.numbers.format(side, crate::units::decimals(side))
+        }
+        Field::Region => country.region.clone(),
+        Field::IsoCodes => country.cca2.clone(),
+        Field::Palette => {
+            country
+                .flag_palette
+                .iter()
+                .map(|(r, g, b)| format!("#{r:02x}{g:02x}{b:02x}"))
+                .collect::<Vec<_>>()
+                .join(" ")
+        }
+        field => {
+            country_display::field_value(country, field, options)
+                .map(|(_, value)| value)
+                .unwrap_or_default()
+        }
+    };
+
+    if value.is_empty() || value == "None" {
+        "-".to_string()
+    } else {
+        value
+    }
+}
+
+/// Whether the `i`th column holds numbers, which are aligned to the right
+fn is_numeric(fields: &[Field], i: usize) -> bool {
+    // The first column is the name of the country
+    i.checked_sub(1)
+        .and_then(|i| fields.get(i))
+        .is_some_and(|field| {
+            matches!(
+                field,
+                Field::Area | Field::Population | Field::Density | Field::SquareSide
+            )
+        })
+}
+
+/// Shrink the widest columns until all of them fit in `available` columns of the terminal
+fn fit(widths: &mut [usize], available: usize) {
+    let gaps = GAP.len() * widths.len().saturating_sub(1);
+
+    while widths.iter().sum::<usize>() + gaps > available {
+        let Some(widest) = widths
+            .iter_mut()
+            .filter(|width| **width > MIN_WIDTH)
+            .max_by_key(|width| **width)
+        else {
+            // Every column is as narrow as it can be
+            break;
+        };
+
+        *widest -= 1;
+    }
+}
+
+/// Pad or truncate `text` to exactly `width` columns
+fn align(text: &str, width: usize, right: bool) -> String {
+    let text = truncate(text, width);
+    let padding = " ".repeat(width.saturating_sub(textwrap::core::display_width(&text)));
+
+    if right {
+        format!("{padding}{text}")
+    } else {
+        format!("{text}{padding}")
+    }
+}
+
+/// Cut `text` to at most `width` columns, ending with `…` when it is too long
+fn truncate(text: &str, width: usize) -> String {
+    if textwrap::core::display_width(text) <= width {
+        return text.to_string();
+    }
+
+    let mut truncated = String::new();
+    let mut used = 0;
+
+    for c in text.chars() {
+        let c_width = textwrap::core::display_width(c.encode_utf8(&mut [0; 4]));
+        if used + c_width + 1 > width {
+            break;
+        }
+        used += c_width;
+        truncated.push(c);
+    }
+
+    truncated.push('…');
+    truncated
+}
@@ -0,0 +1,697 @@
+//! User-defined output with `--template`, for MOTD banners, status bars and custom reports
+//!
+//! Everything outside of braces is printed as-is. `{{` and `}}` print a literal brace.
+//!
+//! ```text
+//! {name.common}                       value at a path, with the same rules as `--get`
+//! {population|human}                  value passed through filters, from left to right
+//! {capital|join:" / "}                filter with an argument
+//! {#if landlocked}...{#else}...{/if}  condition, with the same syntax as `--where`
+//! {#each capital as city}...{/each}   repeat for each item of an array
+//! {#each currencies as code, currency}...{/each}
+//!                                     repeat for each key and value of an object
+//! ```
+//!
+//! Filters: `human`, `join`, `upper`, `lower`, `first`, `last`, `len`, `keys`, `values`,
+//! `round` and `default`.
+//!
+//! A line that only has `{#if}`, `{#else}`, `{/if}`, `{#each}` or `{/each}` on it
+//! does not leave behind an empty line.
+
+use std::borrow::Cow;
+use std::str::FromStr;
+
+use serde_json::Value;
+
+use crate::countries::Country;
+use crate::filter::Filter;
+use crate::numbers::Notation;
+use crate::numbers::NumberFormat;
+use crate::query;
+
+/// A parsed template
+#[derive(Debug)]
+pub struct Template(Vec<Node>);
+
+impl Template {
+    /// Render the template for `country`
+    pub fn render(&self, country: &Country) -> eyre::Result<String> {
+        Ok(self.render_value(&serde_json::to_value(country)?))
+    }
+
+    /// Render the template for the JSON representation of a country
+    fn render_value(&self, country: &Value) -> String {
+        let mut output = String::new();
+        render(
+            &self.0,
+            &mut Scope {
+                country,
+                variables: Vec::new(),
+            },
+            &mut output,
+        );
+
+        output
+    }
+}
+
+impl FromStr for Template {
+    type Err = String;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let mut tokens = tokenize(source)?.into_iter();
+        let (nodes, end) = parse(&mut tokens)?;
+
+        match end {
+            None => Ok(Self(nodes)),
+            Some(tag) => Err(format!("unexpected `{{{}}}`", tag.describe())),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Node {
+    Text(String),
+    Value {
+        path: String,
+        filters: Vec<Pipe>,
+    },
+    If {
+        condition: Filter,
+        then: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+    Each {
+        path: String,
+        /// For arrays, the item. For objects, the value
+        item: String,
+        /// For arrays, the index of the item. For objects, the key
+        key: Option<String>,
+        body: Vec<Node>,
+    },
+}
+
+/// A filter that a value is passed through, such as `join:", "`
+#[derive(Debug)]
+struct Pipe {
+    kind: PipeKind,
+    argument: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum PipeKind {
+    /// Numbers as with `--numbers compact`, e.g. `83.5 million`, with the separators of the locale
+    Human,
+    /// Items of an array or values of an object, separated by the argument or `, `
+    Join,
+    Upper,
+    Lower,
+    First,
+    Last,
+    /// Number of items, keys or characters
+    Len,
+    Keys,
+    Values,
+    /// Round a number to the argument's number of decimal places, or to an integer
+    Round,
+    /// The argument, when the value is missing or empty
+    Default,
+}
+
+impl FromStr for PipeKind {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Ok(match name {
+            "human" => Self::Human,
+            "join" => Self::Join,
+            "upper" => Self::Upper,
+            "lower" => Self::Lower,
+            "first" => Self::First,
+            "last" => Self::Last,
+            "len" => Self::Len,
+            "keys" => Self::Keys,
+            "values" => Self::Values,
+            "round" => Self::Round,
+            "default" => Self::Default,
+            name => return Err(format!("unknown filter `{name}`")),
+        })
+    }
+}
+
+/// Piece of a template before the blocks are matched up
+#[derive(Debug)]
+enum Token {
+    Text(String),
+    Tag(Tag),
+}
+
+#[derive(Debug)]
+enum Tag {
+    Value(String),
+    If(String),
+    Else,
+    EndIf,
+    Each(String),
+    EndEach,
+}
+
+impl Tag {
+    /// For error messages
+    fn describe(&self) -> String {
+        match self {
+            Self::Value(value) => value.clone(),
+            Self::If(condition) => format!("#if {condition}"),
+            Self::Else => "#else".to_string(),
+            Self::EndIf => "/if".to_string(),
+            Self::Each(each) => format!("#each {each}"),
+            Self::EndEach => "/each".to_string(),
+        }
+    }
+
+    fn is_block(&self) -> bool {
+        !matches!(self, Self::Value(_))
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut text = String::new();
+    let mut chars = source.chars().peekable();
+    // Skip the line break after a block tag that is alone on its line
+    let mut skip_line_break = false;
+    // Whether nothing but whitespace has been written since the last line break
+    let mut at_line_start = true;
+
+    while let Some(c) = chars.next() {
+        if skip_line_break {
+            skip_line_break = false;
+            if c == '\n' {
+                continue;
+            }
+        }
+
+        match c {
+            '{' if chars.next_if_eq(&'{').is_some() => text.push('{'),
+            '}' if chars.next_if_eq(&'}').is_some() => text.push('}'),
+            '}' => return Err("unmatched `}`, write `}}` for a literal brace".to_string()),
+            '{' => {
+                let mut inside = String::new();
+                let mut quote = None;
+
+                loop {
+                    match chars.next() {
+                        Some('}') if quote.is_none() => break,
+                        Some(c @ ('"' | '\'')) if quote.is_none() => {
+                            quote = Some(c);
+                            inside.push(c);
+                        }
+                        Some(c) if Some(c) == quote => {
+                            quote = None;
+                            inside.push(c);
+                        }
+                        Some(c) => inside.push(c),
+                        None => return Err(format!("unterminated `{{{inside}`")),
+                    }
+                }
+
+                let tag = parse_tag(inside.trim())?;
+
+                let line_start = text.rfind('\n').map_or(0, |i| i + 1);
+                let is_alone = tag.is_block()
+                    && text[line_start..].trim().is_empty()
+                    && (line_start > 0 || at_line_start)
+                    && chars
+                        .clone()
+                        .take_while(|c| *c != '\n')
+                        .all(char::is_whitespace);
+
+                if is_alone {
+                    text.truncate(line_start);
+                    while chars.next_if(|c| *c != '\n' && c.is_whitespace()).is_some() {}
+                    skip_line_break = true;
+                }
+                at_line_start = is_alone;
+
+                if !text.is_empty() {
+                    tokens.push(Token::Text(std::mem::take(&mut text)));
+                }
+                tokens.push(Token::Tag(tag));
+            }
+            c => text.push(c),
+        }
+    }
+
+    if !text.is_empty() {
+        tokens.push(Token::Text(text));
+    }
+
+    Ok(tokens)
+}
+
+fn parse_tag(tag: &str) -> Result<Tag, String> {
+    Ok(if let Some(condition) = tag.strip_prefix("#if ") {
+        Tag::If(condition.trim().to_string())
+    } else if let Some(each) = tag.strip_prefix("#each ") {
+        Tag::Each(each.trim().to_string())
+    } else {
+        match tag {
+            "#else" => Tag::Else,
+            "/if" => Tag::EndIf,
+            "/each" => Tag::EndEach,
+            "" => return Err("empty `{}`, write `{{}}` for literal braces".to_string()),
+            tag if tag.starts_with(['#', '/']) => return Err(format!("unknown tag `{{{tag}}}`")),
+            tag => Tag::Value(tag.to_string()),
+        }
+    })
+}
+
+/// Parse nodes until the end of the template, or until a tag that closes a block,
+/// which is returned
+fn parse(tokens: &mut impl Iterator<Item = Token>) -> Result<(Vec<Node>, Option<Tag>), String> {
+    let mut nodes = Vec::new();
+
+    while let Some(token) = tokens.next() {
+        let tag = match token {
+            Token::Text(text) => {
+                nodes.push(Node::Text(text));
+                continue;
+            }
+            Token::Tag(tag) => tag,
+        };
+
+        match tag {
+            Tag::Value(value) => nodes.push(parse_value(&value)?),
+            Tag::If(condition) => {
+                let condition = condition
+                    .parse()
+                    .map_err(|error| format!("invalid condition `{condition}`: {error}"))?;
+
+                let (then, end) = parse(tokens)?;
+                let otherwise = match end {
+                    Some(Tag::EndIf) => Vec::new(),
+                    Some(Tag::Else) => {
+                        match parse(tokens)? {
+                            (otherwise, Some(Tag::EndIf)) => otherwise,
+                            _ => return Err("`{#else}` without `{/if}`".to_string()),
+                        }
+                    }
+                    _ => return Err("`{#if}` without `{/if}`".to_string()),
+                };
+
+                nodes.push(Node::If {
+                    condition,
+                    then,
+                    otherwise,
+                });
+            }
+            Tag::Each(each) => {
+                let (path, names) = each
+                    .split_once(" as ")
+                    .ok_or_else(|| format!("expected `{{#each PATH as NAME}}`, found `{each}`"))?;
+
+                let (key, item) = match names.split_once(',') {
+                    Some((key, item)) => (Some(key.trim().to_string()), item.trim().to_string()),
+                    None => (None, names.trim().to_string()),
+                };
+
+                let (body, end) = parse(tokens)?;
+                if !matches!(end, Some(Tag::EndEach)) {
+                    return Err("`{#each}` without `{/each}`".to_string());
+                }
+
+                nodes.push(Node::Each {
+                    path: path.trim().to_string(),
+                    item,
+                    key,
+                    body,
+                });
+            }
+            tag @ (Tag::Else | Tag::EndIf | Tag::EndEach) => return Ok((nodes, Some(tag))),
+        }
+    }
+
+    Ok((nodes, None))
+}
+
+/// Parse `path|filter|filter:argument`
+fn parse_value(value: &str) -> Result<Node, String> {
+    let mut parts = split_pipes(value).into_iter();
+    let path = parts.next().unwrap_or_default().trim().to_string();
+
+    let filters = parts
+        .map(|pipe| {
+            let (name, argument) = match pipe.split_once(':') {
+                Some((name, argument)) => (name, Some(unquote(argument.trim()))),
+                None => (pipe.as_str(), None),
+            };
+
+            Ok(Pipe {
+                kind: name.trim().parse()?,
+                argument,
+            })
+        })
+        .collect::<Result<_, String>>()?;
+
+    Ok(Node::Value { path, filters })
+}
+
+/// Split on `|`, except inside quotes
+fn split_pipes(value: &str) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut quote = None;
+
+    for c in value.chars() {
+        match c {
+            '|' if quote.is_none() => {
+                parts.push(String::new());
+                continue;
+            }
+            '"' | '\'' if quote.is_none() => quote = Some(c),
+            c if Some(c) == quote => quote = None,
+            _ => (),
+        }
+
+        if let Some(part) = parts.last_mut() {
+            part.push(c);
+        }
+    }
+
+    parts
+}
+
+/// Argument of a filter without its quotes, with `\n` and `\t` escapes
+fn unquote(argument: &str) -> String {
+    let unquoted = ['"', '\'']
+        .iter()
+        .find_map(|quote| {
+            argument
+                .strip_prefix(*quote)
+                .and_then(|argument| argument.strip_suffix(*quote))
+        })
+        .unwrap_or(argument);
+
+    unquoted.replace("\\n", "\n").replace("\\t", "\t")
+}
+
+/// Values that can be looked up while rendering
+struct Scope<'a> {
+    country: &'a Value,
+    /// Variables of `{#each}`, the innermost last
+    variables: Vec<(String, Value)>,
+}
+
+impl Scope<'_> {
+    /// Value at `path`, which starts with a variable or is a path into the country
+    fn get(&self, path: &str) -> Option<Cow<'_, Value>> {
+        let (name, rest) = path.split_once('.').unwrap_or((path, ""));
+
+        match self
+            .variables
+            .iter()
+            .rev()
+            .find(|(variable, _)| variable == name)
+        {
+            Some((_, value)) if rest.is_empty() => Some(Cow::Borrowed(value)),
+            Some((_, value)) => query::get(value, rest).map(Cow::Borrowed),
+            None => query::get(self.country, path).map(Cow::Borrowed),
+        }
+    }
+
+    /// The country, with the variables added so that conditions can refer to them
+    fn condition_context(&self) -> Cow<'_, Value> {
+        if self.variables.is_empty() {
+            return Cow::Borrowed(self.country);
+        }
+
+        let mut context = self.country.clone();
+        if let Some(map) = context.as_object_mut() {
+            for (name, value) in &self.variables {
+                map.insert(name.clone(), value.clone());
+            }
+        }
+
+        Cow::Owned(context)
+    }
+}
+
+fn render(nodes: &[Node], scope: &mut Scope, output: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => output.push_str(text),
+            Node::Value { path, filters } => {
+                let value = scope.get(path).map(Cow::into_owned);
+                let value = filters.iter().fold(value, |value, pipe| apply(pipe, value));
+
+                if let Some(value) = value {
+                    output.push_str(&display(&value));
+                }
+            }
+            Node::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                let matches = condition.matches_value(&scope.condition_context());
+                render(if matches { then } else { otherwise }, scope, output);
+            }
+            Node::Each {
+                path,
+                item,
+                key,
+                body,
+            } => {
+                let entries = match scope.get(path).map(Cow::into_owned) {
+                    Some(Value::Array(values)) => {
+                        values
+                            .into_iter()
+                            .enumerate()
+                            .map(|(i, value)| (Value::from(i), value))
+                            .collect()
+                    }
+                    Some(Value::Object(map)) => {
+                        map.into_iter()
+                            .map(|(key, value)| (Value::String(key), value))
+                            .collect()
+                    }
+                    Some(value) => vec![(Value::from(0), value)],
+                    None => Vec::new(),
+                };
+
+                for (entry_key, value) in entries {
+                    let variables = scope.variables.len();
+
+                    if let Some(key) = key {
+                        scope.variables.push((key.clone(), entry_key));
+                    }
+                    scope.variables.push((item.clone(), value));
+
+                    render(body, scope, output);
+
+                    scope.variables.truncate(variables);
+                }
+            }
+        }
+    }
+}
+
+/// Pass `value` through a filter
+fn apply(pipe: &Pipe, value: Option<Value>) -> Option<Value> {
+    let argument = pipe.argument.as_deref();
+
+    if let PipeKind::Default = pipe.kind {
+        // Values that are not empty are passed through unchanged
+        let is_empty = match &value {
+            None => true,
+            Some(Value::String(string)) => string.is_empty(),
+            Some(Value::Array(values)) => values.is_empty(),
+            Some(Value::Object(map)) => map.is_empty(),
+            Some(_) => false,
+        };
+
+        return if is_empty {
+            Some(Value::String(argument.unwrap_or_default().to_string()))
+        } else {
+            value
+        };
+    }
+
+    let value = value?;
+
+    Some(match pipe.kind {
+        PipeKind::Human => {
+            match value.as_f64() {
+                Some(number) => {
+                    Value::String(NumberFormat::new(Notation::Compact).format(number, 1))
+                }
+                None => value,
+            }
+        }
+        PipeKind::Join => {
+            let separator = argument.unwrap_or(", ");
+            let items = match &value {
+                Value::Array(values) => values.iter().map(display).collect::<Vec<_>>(),
+                Value::Object(map) => map.values().map(display).collect(),
+                value => vec![display(value)],
+            };
+            Value::String(items.join(separator))
+        }
+        PipeKind::Upper => Value::String(display(&value).to_uppercase()),
+        PipeKind::Lower => Value::String(display(&value).to_lowercase()),
+        PipeKind::First | PipeKind::Last => {
+            let mut items: Box<dyn DoubleEndedIterator<Item = Value>> = match value {
+                Value::Array(values) => Box::new(values.into_iter()),
+                Value::Object(map) => Box::new(map.into_iter().map(|(_, value)| value)),
+                value => Box::new(std::iter::once(value)),
+            };
+
+            if let PipeKind::First = pipe.kind {
+                items.next()?
+            } else {
+                items.next_back()?
+            }
+        }
+        PipeKind::Len => {
+            Value::from(match &value {
+                Value::Array(values) => values.len(),
+                Value::Object(map) => map.len(),
+                value => display(value).chars().count(),
+            })
+        }
+        PipeKind::Keys => {
+            match value {
+                Value::Object(map) => map.into_iter().map(|(key, _)| Value::String(key)).collect(),
+                _ => return None,
+            }
+        }
+        PipeKind::Values => {
+            match value {
+                Value::Object(map) => map.into_iter().map(|(_, value)| value).collect(),
+                value => value,
+            }
+        }
+        PipeKind::Round => {
+            let decimals = argument
+                .and_then(|argument| argument.parse().ok())
+                .unwrap_or(0);
+            match value.as_f64() {
+                Some(number) => Value::String(format!("{number:.decimals$}")),
+                None => value,
+            }
+        }
+        PipeKind::Default => value,
+    })
+}
+
+/// Value as it is printed: strings without quotes, and arrays as a list
+fn display(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(string) => string.clone(),
+        Value::Array(values) => values.iter().map(display).collect::<Vec<_>>().join(", "),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn render(source: &str) -> String {
+        let country = json!({
+            "name": { "common": "Japan" },
+            "population": 123_210_000,
+            "landlocked": false,
+            "capital": ["Tokyo"],
+            "tld": [".jp", ".みんな"],
+            "currencies": { "JPY": { "name": "Japanese yen", "symbol": "¥" } },
+        });
+
+        source
+            .parse::<Template>()
+            .unwrap_or_else(|error| panic!("failed to parse `{source}`: {error}"))
+            .render_value(&country)
+    }
+
+    fn error(source: &str) -> String {
+        source.parse::<Template>().unwrap_err()
+    }
+
+    #[test]
+    fn values_and_filters() {
+        assert_eq!(render("{name.common}!"), "Japan!");
+        assert_eq!(render("{name.common|upper}"), "JAPAN");
+        assert_eq!(render(r#"{tld|join:" | "}"#), ".jp | .みんな");
+        assert_eq!(render("{tld|first}, {tld|len}"), ".jp, 2");
+        assert_eq!(render(r#"{missing|default:"none"}"#), "none");
+        assert_eq!(render("{missing}"), "");
+        assert_eq!(
+            render("{population|human}"),
+            NumberFormat::new(Notation::Compact).format(123_210_000.0, 1)
+        );
+    }
+
+    #[test]
+    fn literal_braces() {
+        assert_eq!(render("{{name.common}}"), "{name.common}");
+        assert_eq!(render(r#"{tld|join:"}"}"#), ".jp}.みんな");
+    }
+
+    #[test]
+    fn conditions() {
+        assert_eq!(render("{#if landlocked}yes{#else}no{/if}"), "no");
+        assert_eq!(render("{#if !landlocked && capital}yes{/if}"), "yes");
+        assert_eq!(
+            render(r#"{#if "JPY" in currencies}{#if landlocked}a{#else}b{/if}{/if}"#),
+            "b"
+        );
+    }
+
+    #[test]
+    fn loops() {
+        assert_eq!(render("{#each tld as tld}[{tld}]{/each}"), "[.jp][.みんな]");
+        assert_eq!(
+            render("{#each currencies as code, currency}{code}: {currency.symbol}{/each}"),
+            "JPY: ¥"
+        );
+        assert_eq!(
+            render(r#"{#each tld as tld}{#if tld == ".jp"}{tld}{/if}{/each}"#),
+            ".jp"
+        );
+    }
+
+    #[test]
+    fn block_tags_alone_on_a_line_leave_no_blank_line() {
+        let source = "Capitals:\n{#each capital as city}\n  - {city}\n{/each}\nDone\n";
+        assert_eq!(render(source), "Capitals:\n  - Tokyo\nDone\n");
+
+        let source = "  {#if landlocked}  \nlandlocked\n  {#else}\ncoastal\n{/if}\n";
+        assert_eq!(render(source), "coastal\n");
+    }
+
+    #[test]
+    fn block_tags_next_to_text_are_kept_inline() {
+        assert_eq!(render("a {#if capital}b{/if} c\nd\n"), "a b c\nd\n");
+        assert_eq!(render("{#if capital}x{/if}\ny"), "x\ny");
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(error("{/if}"), "unexpected `{/if}`");
+        assert_eq!(error("{#if capital}{/each}"), "`{#if}` without `{/if}`");
+        assert_eq!(error("{#if capital}"), "`{#if}` without `{/if}`");
+        assert_eq!(error("{#if capital}{#else}"), "`{#else}` without `{/if}`");
+        assert_eq!(error("{#each tld as x}"), "`{#each}` without `{/each}`");
+        assert_eq!(error("{name.common"), "unterminated `{name.common`");
+        assert_eq!(
+            error("a } b"),
+            "unmatched `}`, write `}}` for a literal brace"
+        );
+        assert_eq!(error("{}"), "empty `{}`, write `{{}}` for literal braces");
+        assert_eq!(error("{#for x}"), "unknown tag `{#for x}`");
+        assert_eq!(error("{name|shout}"), "unknown filter `shout`");
+        assert!(error(r#"{#if region == "Asia}{/if}"#).contains("unterminated"));
+    }
+}
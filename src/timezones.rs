@@ -0,0 +1,105 @@
+//! The time in the timezones of a country, now or at the moment given with `--at`
+//!
+//! Everything is computed from the UTC offsets that are in the data,
+//! such as `UTC+09:00`, so no timezone database is needed.
+
+use jiff::Timestamp;
+use jiff::Zoned;
+use jiff::civil::DateTime;
+use jiff::civil::Time;
+use jiff::tz::Offset;
+use jiff::tz::TimeZone;
+
+/// Each timezone with the time there, and how far it is ahead of or behind `local`,
+/// e.g. `UTC+09:00 18:30 (+7h)`. The parts of a timezone are joined with non-breaking
+/// spaces, so that it is never wrapped onto two lines
+pub fn describe(timezones: &[String], moment: Timestamp, local: &TimeZone) -> String {
+    let local_offset = local.to_offset(moment);
+    let local_date = local_offset.to_datetime(moment).date();
+
+    timezones
+        .iter()
+        .map(|timezone| {
+            let Some(offset) = parse_offset(timezone) else {
+                return timezone.clone();
+            };
+
+            let there = offset.to_datetime(moment);
+
+            // The weekday is only shown when it is a different day there
+            let time = if there.date() == local_date {
+                there.strftime("%H:%M")
+            } else {
+                there.strftime("%a\u{a0}%H:%M")
+            };
+
+            format!(
+                "{timezone}\u{a0}{time}\u{a0}({})",
+                difference(offset.seconds() - local_offset.seconds())
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Parse a timezone of a country, such as `UTC+05:30`, `UTC-03:00` or `UTC`
+fn parse_offset(timezone: &str) -> Option<Offset> {
+    let offset = timezone.strip_prefix("UTC")?;
+
+    if offset.is_empty() {
+        return Some(Offset::UTC);
+    }
+
+    let (sign, offset) = match offset.split_at_checked(1)? {
+        ("+", offset) => (1, offset),
+        ("-", offset) => (-1, offset),
+        _ => return None,
+    };
+
+    let (hours, minutes) = offset.split_once(':').unwrap_or((offset, "0"));
+    let seconds = (hours.parse::<i32>().ok()? * 60 + minutes.parse::<i32>().ok()?) * 60;
+
+    Offset::from_seconds(sign * seconds).ok()
+}
+
+/// How much later it is, e.g. `+7h`, `-3h 30m` or `same time`
+fn difference(seconds: i32) -> String {
+    if seconds == 0 {
+        return "same time".to_string();
+    }
+
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let minutes = seconds.unsigned_abs() / 60;
+
+    match (minutes / 60, minutes % 60) {
+        (hours, 0) => format!("{sign}{hours}h"),
+        (0, minutes) => format!("{sign}{minutes}m"),
+        (hours, minutes) => format!("{sign}{hours}h\u{a0}{minutes}m"),
+    }
+}
+
+/// Parse a moment for `--at`, such as `2026-10-18 09:00` or just `09:00` for today,
+/// in the local timezone. An offset can be given too, e.g. `2026-10-18T09:00+02:00`
+pub fn parse_moment(moment: &str) -> Result<Timestamp, String> {
+    if let Ok(timestamp) = moment.parse::<Timestamp>() {
+        return Ok(timestamp);
+    }
+
+    if let Ok(zoned) = moment.parse::<Zoned>() {
+        return Ok(zoned.timestamp());
+    }
+
+    let datetime = match moment.parse::<Time>() {
+        Ok(time) => Zoned::now().date().to_datetime(time),
+        Err(_) => {
+            moment.parse::<DateTime>().map_err(|_| {
+                format!("expected a time such as `2026-10-18 09:00` or `09:00`, found `{moment}`")
+            })?
+        }
+    };
+
+    datetime
+        .to_zoned(TimeZone::system())
+        .map(|zoned| zoned.timestamp())
+        .map_err(|error| error.to_string())
+}
@@ -0,0 +1,113 @@
+//! Units of area, density and length, with `--units`
+
+use crate::numbers::NumberFormat;
+
+/// Square miles in one square kilometer
+const SQUARE_MILES_PER_SQUARE_KM: f64 = 0.386_102_158_6;
+
+/// Miles in one kilometer
+const MILES_PER_KM: f64 = 0.621_371_192;
+
+/// Which units are shown
+#[derive(clap::ValueEnum, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum Units {
+    /// Square kilometers and kilometers
+    Metric,
+    /// Square miles and miles
+    Imperial,
+    /// Metric, followed by imperial in parentheses
+    #[default]
+    Both,
+}
+
+impl Units {
+    /// Area given in km², e.g. `357,114 km² (137,882 miles²)`
+    pub fn area(self, km2: f64, numbers: &NumberFormat) -> String {
+        let miles2 = km2 * SQUARE_MILES_PER_SQUARE_KM;
+
+        self.choose(
+            format!("{} km²", numbers.format(km2, decimals(km2))),
+            format!("{} miles²", numbers.format(miles2, decimals(miles2))),
+        )
+    }
+
+    /// Population density given in people per km²
+    pub fn density(self, per_km2: f64, numbers: &NumberFormat) -> String {
+        let per_km2_text = numbers.format(per_km2, 1);
+        let per_mile2_text = numbers.format(per_km2 / SQUARE_MILES_PER_SQUARE_KM, 1);
+
+        match self {
+            Self::Metric => format!("{per_km2_text} people per km²"),
+            Self::Imperial => format!("{per_mile2_text} people per mile²"),
+            Self::Both => format!("{per_km2_text} people per km² ({per_mile2_text} per mile²)"),
+        }
+    }
+
+    /// Length given in km
+    pub fn length(self, km: f64, numbers: &NumberFormat) -> String {
+        let miles = km * MILES_PER_KM;
+
+        self.choose(
+            format!("{} km", numbers.format(km, decimals(km))),
+            format!("{} miles", numbers.format(miles, decimals(miles))),
+        )
+    }
+
+    /// Unit of area, for titles such as `Area (km²)`. With both units, it is the metric one
+    pub fn area_unit(self) -> &'static str {
+        match self {
+            Self::Metric | Self::Both => "km²",
+            Self::Imperial => "miles²",
+        }
+    }
+
+    /// Unit of length, for titles. With both units, it is the metric one
+    pub fn length_unit(self) -> &'static str {
+        match self {
+            Self::Metric | Self::Both => "km",
+            Self::Imperial => "miles",
+        }
+    }
+
+    /// Area in [`Self::area_unit`], as a plain number
+    pub fn area_value(self, km2: f64) -> f64 {
+        match self {
+            Self::Metric | Self::Both => km2,
+            Self::Imperial => km2 * SQUARE_MILES_PER_SQUARE_KM,
+        }
+    }
+
+    /// Population density in people per [`Self::area_unit`], as a plain number
+    pub fn density_value(self, per_km2: f64) -> f64 {
+        match self {
+            Self::Metric | Self::Both => per_km2,
+            Self::Imperial => per_km2 / SQUARE_MILES_PER_SQUARE_KM,
+        }
+    }
+
+    /// Length in [`Self::length_unit`], as a plain number
+    pub fn length_value(self, km: f64) -> f64 {
+        match self {
+            Self::Metric | Self::Both => km,
+            Self::Imperial => km * MILES_PER_KM,
+        }
+    }
+
+    fn choose(self, metric: String, imperial: String) -> String {
+        match self {
+            Self::Metric => metric,
+            Self::Imperial => imperial,
+            Self::Both => format!("{metric} ({imperial})"),
+        }
+    }
+}
+
+/// How many decimals are shown for an area or length, so that small countries such as
+/// Vatican City do not become `0`: 2 below 10, 1 below 100 and none otherwise
+pub fn decimals(value: f64) -> usize {
+    match value.abs() {
+        value if value < 10.0 => 2,
+        value if value < 100.0 => 1,
+        _ => 0,
+    }
+}